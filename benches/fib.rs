@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use divan::Bencher;
-use xstm::{Context, Stm, StmError, TVar, Transaction};
+use xstm::{Algorithm, Context, Stm, StmError, TVar, Transaction};
 
 struct Fib {
     var_a: Arc<TVar<u128>>,
@@ -11,9 +11,9 @@ struct Fib {
 impl Transaction for Fib {
     type Output = u128;
 
-    fn atomically<'this: 'var, 'context, 'var>(
-        &'this self,
-        context: &'context mut Context<'var>,
+    fn atomically<'var, A: Algorithm>(
+        &'var self,
+        context: &mut Context<'var, A>,
     ) -> Result<Self::Output, StmError> {
         let a = context.read(&self.var_a)?;
         let b = context.read(&self.var_b)?;
@@ -15,7 +15,9 @@ const VARS_COUNT: usize = 10;
 #[divan::bench_group(threads = thread_counts())]
 mod stm {
     use divan::Bencher;
-    use xstm::{Context, Stm, StmError, TVar, Transaction};
+    use xstm::{
+        Algorithm, ClockStrategy, Context, NOrec, Stm, StmError, TVar, Tl2, Transaction,
+    };
 
     use crate::VARS_COUNT;
 
@@ -26,9 +28,9 @@ mod stm {
     impl<'a> Transaction for Vars<'a> {
         type Output = ();
 
-        fn atomically<'this: 'var, 'context, 'var>(
-            &'this self,
-            context: &'context mut Context<'var>,
+        fn atomically<'var, A: Algorithm>(
+            &'var self,
+            context: &mut Context<'var, A>,
         ) -> Result<Self::Output, StmError> {
             for var in self.vars {
                 let x = context.read(var)?;
@@ -46,9 +48,9 @@ mod stm {
     impl<'a> Transaction for Sum<'a> {
         type Output = i32;
 
-        fn atomically<'this: 'var, 'context, 'var>(
-            &'this self,
-            context: &'context mut Context<'var>,
+        fn atomically<'var, A: Algorithm>(
+            &'var self,
+            context: &mut Context<'var, A>,
         ) -> Result<Self::Output, StmError> {
             let mut sum = 0;
             for var in self.vars {
@@ -60,13 +62,13 @@ mod stm {
         }
     }
 
-    #[divan::bench]
-    fn write(bencher: Bencher) {
+    #[divan::bench(types = [Tl2, NOrec])]
+    fn write<A: Algorithm + Default + Sync>(bencher: Bencher) {
         let vars = std::iter::repeat_n(1, VARS_COUNT)
             .map(|_| TVar::new(1))
             .collect::<Vec<_>>();
 
-        let stm = Stm::new();
+        let stm = Stm::<A>::default();
 
         bencher.bench(|| {
             let vars = Vars { vars: &vars };
@@ -74,13 +76,32 @@ mod stm {
         });
     }
 
-    #[divan::bench]
-    fn read(bencher: Bencher) {
+    #[divan::bench(args = [
+        ClockStrategy::Gv1,
+        ClockStrategy::Gv4,
+        ClockStrategy::Gv5,
+        ClockStrategy::Gv6,
+    ])]
+    fn write_clock(bencher: Bencher, strategy: ClockStrategy) {
+        let vars = std::iter::repeat_n(1, VARS_COUNT)
+            .map(|_| TVar::new(1))
+            .collect::<Vec<_>>();
+
+        let stm = Stm::with_algorithm(Tl2::new().clock(strategy));
+
+        bencher.bench(|| {
+            let vars = Vars { vars: &vars };
+            stm.atomically(vars);
+        });
+    }
+
+    #[divan::bench(types = [Tl2, NOrec])]
+    fn read<A: Algorithm + Default + Sync>(bencher: Bencher) {
         let vars = std::iter::repeat_n(1, VARS_COUNT)
             .map(|_| TVar::new(1))
             .collect::<Vec<_>>();
 
-        let stm = Stm::new();
+        let stm = Stm::<A>::default();
 
         bencher.bench(|| {
             let sum = Sum { vars: &vars };
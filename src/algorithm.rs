@@ -0,0 +1,134 @@
+use std::time::Instant;
+
+use crate::{CommitInfo, Contention, SafeRead, StmError, TVar};
+
+mod any_var;
+mod write_set;
+
+mod norec;
+pub use norec::NOrec;
+
+mod tl2;
+pub use tl2::Tl2;
+
+mod sealed {
+    pub trait Sealed {}
+
+    impl Sealed for super::NOrec {}
+    impl Sealed for super::Tl2 {}
+}
+
+/// A software transactional memory algorithm used by [`Stm`](crate::Stm)
+///
+/// The algorithm owns the global state (like a version clock),
+/// and decides how the transactions read, write and commit `TVar`s.
+/// [`Tl2`] is the default one.
+///
+/// It's sealed, only the algorithms of this crate implement it,
+/// since a `TVar` only exposes its internals to them.
+pub trait Algorithm: sealed::Sealed {
+    /// The state of a running transaction
+    ///
+    /// It is created once in [`Stm::atomically`](crate::Stm::atomically),
+    /// and reused by all attempts of the transaction
+    type Context<'var>;
+
+    /// The state of a transaction that the later writes can be rolled back to
+    type Checkpoint;
+
+    fn new_context<'var>(&'var self) -> Self::Context<'var>;
+
+    /// Prepare the context for a new attempt
+    fn reset<'var>(&'var self, context: &mut Self::Context<'var>);
+
+    fn read<'var, T: SafeRead>(
+        &self,
+        context: &mut Self::Context<'var>,
+        var: &'var TVar<T>,
+    ) -> Result<T, StmError>;
+
+    /// Run `f` on the value of `var`
+    ///
+    /// The value in the write log is used in place, the committed one is copied once
+    /// unless nobody else can write it until `f` returns
+    fn inspect<'var, T: SafeRead, R>(
+        &self,
+        context: &mut Self::Context<'var>,
+        var: &'var TVar<T>,
+        f: impl FnOnce(&T) -> R,
+    ) -> Result<R, StmError> {
+        self.read(context, var).map(|value| f(&value))
+    }
+
+    /// Ask `contention` before waiting for a busy lock
+    fn write<'var, T: SafeRead>(
+        &self,
+        context: &mut Self::Context<'var>,
+        var: &'var TVar<T>,
+        value: T,
+        contention: &Contention,
+    ) -> Result<(), StmError>;
+
+    /// Run `f` on the value of `var` to change it, like a read followed by a write
+    ///
+    /// Ask `contention` before waiting for a busy lock
+    fn update<'var, T: SafeRead, R>(
+        &self,
+        context: &mut Self::Context<'var>,
+        var: &'var TVar<T>,
+        f: impl FnOnce(&mut T) -> R,
+        contention: &Contention,
+    ) -> Result<R, StmError> {
+        let mut value = self.read(context, var)?;
+        let result = f(&mut value);
+        self.write(context, var, value, contention)?;
+
+        Ok(result)
+    }
+
+    /// Try to make the writes of the attempt visible to other transactions
+    ///
+    /// Ask `contention` before waiting for a busy lock
+    fn commit(
+        &self,
+        context: &mut Self::Context<'_>,
+        contention: &Contention,
+    ) -> Result<CommitInfo, StmError>;
+
+    /// Release what the failed attempt holds, e.g. the locks taken before committing,
+    /// called before the contention manager delays the next attempt
+    fn abort(&self, _context: &mut Self::Context<'_>) {}
+
+    /// Whether the next attempt runs in another context, e.g. switched from read-only to write
+    fn switches_context(&self, _context: &Self::Context<'_>) -> bool {
+        false
+    }
+
+    /// Whether the failed attempt conflicted with other transactions,
+    /// `false` if the algorithm gave it up for itself (e.g. to switch the context)
+    fn conflicted(&self, _context: &Self::Context<'_>) -> bool {
+        true
+    }
+
+    /// Make the attempt irrevocable, it's called after [`reset`](Algorithm::reset)
+    ///
+    /// No other transaction can commit until [`end_irrevocable`](Algorithm::end_irrevocable),
+    /// the attempt waits for the busy locks and its commit must not fail
+    fn begin_irrevocable<'var>(&'var self, context: &mut Self::Context<'var>);
+
+    /// Let other transactions commit again,
+    /// called after the irrevocable attempt committed or stopped
+    fn end_irrevocable(&self, context: &mut Self::Context<'_>);
+
+    /// Block until one of the `TVar`s read in the attempt was changed,
+    /// or the deadline passed
+    fn wait(&self, context: &mut Self::Context<'_>, deadline: Option<Instant>);
+
+    fn checkpoint(&self, context: &mut Self::Context<'_>) -> Self::Checkpoint;
+
+    /// Keep the writes after checkpoint
+    fn release(&self, context: &mut Self::Context<'_>, checkpoint: Self::Checkpoint);
+
+    /// Discard the writes after checkpoint
+    fn rollback(&self, context: &mut Self::Context<'_>, checkpoint: Self::Checkpoint);
+}
@@ -1,13 +1,15 @@
-use crate::{versioned_lock::VersionedLock, TVar};
+use crate::{meta::LazyMeta, versioned_lock::VersionedLock, SafeRead, TVar, TVarId};
 
 /// TVar without generic T
 #[derive(Clone, Copy)]
 pub struct AnyTVar<'var> {
     // the pointer to TVar (value.as_ptr())
-    // Cannot deref, used in comparing
+    // Used in comparing, it can only be dereferenced by the value-based algorithms
     pub ptr: *const (),
     // the lock in TVar
     pub lock: &'var VersionedLock,
+    // the waiters, history and name of TVar
+    pub meta: &'var LazyMeta,
 }
 
 impl<'var> PartialEq for AnyTVar<'var> {
@@ -17,15 +19,20 @@ impl<'var> PartialEq for AnyTVar<'var> {
 }
 
 impl<'var> AnyTVar<'var> {
-    pub fn from_var<T: Copy>(var: &'var TVar<T>) -> AnyTVar<'var> {
+    pub fn id(&self) -> TVarId {
+        TVarId::new(self.ptr, self.meta.name())
+    }
+
+    pub fn from_var<T: SafeRead>(var: &'var TVar<T>) -> AnyTVar<'var> {
         AnyTVar {
             ptr: var.value_ptr() as *const _,
             lock: var.get_lock(),
+            meta: var.get_meta(),
         }
     }
 }
 
-impl<'var, T: Copy> From<&'var TVar<T>> for AnyTVar<'var> {
+impl<'var, T: SafeRead> From<&'var TVar<T>> for AnyTVar<'var> {
     fn from(value: &'var TVar<T>) -> Self {
         AnyTVar::from_var(value)
     }
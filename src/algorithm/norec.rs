@@ -0,0 +1,345 @@
+use std::{
+    mem::MaybeUninit,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use crate::{waiter::Waiter, AbortReason, CommitInfo, Contention, SafeRead, StmError, TVar, TVarId};
+
+use super::{
+    write_set::{Checkpoint, WriteSet},
+    Algorithm,
+};
+
+mod read_set;
+use read_set::ReadSet;
+
+/// NOrec (No Ownership Records)
+///
+/// A single global sequence lock and value-based validation,
+/// the per-`TVar` versioned locks are never used.
+/// They are still stored in every `TVar`, whatever the algorithm is,
+/// so a `TVar` takes the same memory as with [`Tl2`](crate::Tl2).
+/// Only one transaction can commit at a time,
+/// but reading never touches any shared metadata except the sequence lock,
+/// so it suits the workloads with many readers and few writers.
+/// An [irrevocable](crate::Stm::irrevocably) transaction holds the sequence lock
+/// while it runs, so it stops the readers too.
+pub struct NOrec {
+    // odd means a transaction is committing
+    sequence: AtomicUsize,
+    // the sequence is held by an irrevocable transaction, for as long as it runs
+    irrevocable: AtomicBool,
+}
+
+impl NOrec {
+    pub fn new() -> Self {
+        NOrec {
+            sequence: AtomicUsize::new(0),
+            irrevocable: AtomicBool::new(false),
+        }
+    }
+
+    /// Wait until no transaction is committing
+    fn sample(&self) -> usize {
+        loop {
+            let sequence = self.sequence.load(Ordering::SeqCst);
+
+            if sequence & 1 == 0 {
+                return sequence;
+            }
+
+            if self.irrevocable.load(Ordering::Relaxed) {
+                // It may run for long, e.g. doing I/O
+                std::thread::yield_now();
+            } else {
+                std::hint::spin_loop();
+            }
+        }
+    }
+
+    /// Validate the read set, return the new snapshot if it's still valid
+    /// or the changed TVar
+    fn validate(&self, read_set: &ReadSet<'_>) -> Result<usize, TVarId> {
+        loop {
+            let snapshot = self.sample();
+
+            read_set.validate()?;
+
+            // Nobody committed while validating
+            if self.sequence.load(Ordering::SeqCst) == snapshot {
+                return Ok(snapshot);
+            }
+        }
+    }
+
+    /// The sequence after the commit is the write version
+    fn commit_info(context: &Context<'_>) -> CommitInfo {
+        CommitInfo::new(
+            context.read_set.len(),
+            context.write_set.len(),
+            Some(context.snapshot as u64 + 2),
+        )
+    }
+}
+
+impl Default for NOrec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The transaction context of [`NOrec`]
+pub struct Context<'var> {
+    snapshot: usize,
+    // holding the sequence lock since the start of the attempt
+    irrevocable: bool,
+    write_set: WriteSet<'var>,
+    read_set: ReadSet<'var>,
+}
+
+impl Algorithm for NOrec {
+    type Context<'var> = Context<'var>;
+    type Checkpoint = Checkpoint;
+
+    fn new_context<'var>(&'var self) -> Self::Context<'var> {
+        Context {
+            snapshot: self.sample(),
+            irrevocable: false,
+            write_set: WriteSet::new(),
+            read_set: ReadSet::new(),
+        }
+    }
+
+    fn reset<'var>(&'var self, context: &mut Self::Context<'var>) {
+        context.snapshot = self.sample();
+        context.write_set.clear();
+        context.read_set.clear();
+    }
+
+    fn read<'var, T: SafeRead>(
+        &self,
+        context: &mut Self::Context<'var>,
+        var: &'var TVar<T>,
+    ) -> Result<T, StmError> {
+        // Check we wrote before
+        if let Some(wrote_value) = context.write_set.try_read(var) {
+            return Ok(wrote_value);
+        }
+
+        loop {
+            let value = var.read_unchecked();
+
+            if context.irrevocable || self.sequence.load(Ordering::SeqCst) == context.snapshot {
+                context.read_set.log(var, &value);
+
+                // Nobody committed since the snapshot, the value is consistent
+                return Ok(unsafe { value.assume_init() });
+            }
+
+            // Someone committed, extend the snapshot if the read set is still valid
+            context.snapshot = self.validate(&context.read_set).map_err(|var| {
+                StmError::Retry(AbortReason::ReadValidation { var: Some(var) })
+            })?;
+        }
+    }
+
+    fn inspect<'var, T: SafeRead, R>(
+        &self,
+        context: &mut Self::Context<'var>,
+        var: &'var TVar<T>,
+        f: impl FnOnce(&T) -> R,
+    ) -> Result<R, StmError> {
+        // Check we wrote before
+        let f = match context.write_set.try_inspect(var, f) {
+            Ok(result) => return Ok(result),
+            Err(f) => f,
+        };
+
+        if context.irrevocable {
+            // Nobody can write it while we hold the sequence, run f in place
+            context.read_set.log_in_place(var);
+
+            return Ok(f(unsafe { &*var.value_ptr() }));
+        }
+
+        loop {
+            let value = var.read_unchecked();
+
+            if context.irrevocable || self.sequence.load(Ordering::SeqCst) == context.snapshot {
+                context.read_set.log(var, &value);
+
+                // Nobody committed since the snapshot, the value is consistent
+                return Ok(f(unsafe { value.assume_init_ref() }));
+            }
+
+            // Someone committed, extend the snapshot if the read set is still valid
+            context.snapshot = self.validate(&context.read_set).map_err(|var| {
+                StmError::Retry(AbortReason::ReadValidation { var: Some(var) })
+            })?;
+        }
+    }
+
+    fn update<'var, T: SafeRead, R>(
+        &self,
+        context: &mut Self::Context<'var>,
+        var: &'var TVar<T>,
+        f: impl FnOnce(&mut T) -> R,
+        _: &Contention,
+    ) -> Result<R, StmError> {
+        let irrevocable = context.irrevocable;
+        let snapshot = &mut context.snapshot;
+        let read_set = &mut context.read_set;
+
+        // Copy the current value to write set once, it's changed there later
+        let fill = |dst: *mut T| loop {
+            unsafe { var.read_unchecked_into(dst) };
+
+            if irrevocable || self.sequence.load(Ordering::SeqCst) == *snapshot {
+                // Nobody committed since the snapshot, the value is consistent
+                read_set.log(var, unsafe { &*(dst as *const MaybeUninit<T>) });
+                return Ok(());
+            }
+
+            // Someone committed, extend the snapshot if the read set is still valid
+            *snapshot = self.validate(read_set).map_err(|var| {
+                StmError::Retry(AbortReason::ReadValidation { var: Some(var) })
+            })?;
+        };
+
+        context.write_set.update(var, fill, f)
+    }
+
+    fn write<'var, T: SafeRead>(
+        &self,
+        context: &mut Self::Context<'var>,
+        var: &'var TVar<T>,
+        value: T,
+        _: &Contention,
+    ) -> Result<(), StmError> {
+        context.write_set.log(var, value);
+
+        Ok(())
+    }
+
+    fn commit(
+        &self,
+        context: &mut Self::Context<'_>,
+        _: &Contention,
+    ) -> Result<CommitInfo, StmError> {
+        if context.irrevocable {
+            // Safety: we are the only committing transaction
+            unsafe {
+                context.write_set.write_data_from_buffer();
+            }
+
+            context.irrevocable = false;
+            self.irrevocable.store(false, Ordering::Relaxed);
+            self.sequence.store(context.snapshot + 2, Ordering::SeqCst);
+
+            context.write_set.notify_waiters();
+
+            return Ok(Self::commit_info(context));
+        }
+
+        if context.write_set.is_empty() {
+            // The reads were consistent, committing a read-only transaction is always successful
+            return Ok(CommitInfo::new(context.read_set.len(), 0, None));
+        }
+
+        // lock the sequence
+        while self
+            .sequence
+            .compare_exchange(
+                context.snapshot,
+                context.snapshot + 1,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            )
+            .is_err()
+        {
+            context.snapshot = self.validate(&context.read_set).map_err(|var| {
+                StmError::Retry(AbortReason::CommitValidation { var: Some(var) })
+            })?;
+        }
+
+        // Safety: we are the only committing transaction
+        unsafe {
+            context.write_set.write_data_from_buffer();
+        }
+
+        // unlock the sequence
+        self.sequence.store(context.snapshot + 2, Ordering::SeqCst);
+
+        context.write_set.notify_waiters();
+
+        Ok(Self::commit_info(context))
+    }
+
+    fn begin_irrevocable<'var>(&'var self, context: &mut Self::Context<'var>) {
+        // lock the sequence for the whole attempt
+        loop {
+            let snapshot = self.sample();
+
+            if self
+                .sequence
+                .compare_exchange(snapshot, snapshot + 1, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                context.snapshot = snapshot;
+                context.irrevocable = true;
+                self.irrevocable.store(true, Ordering::Relaxed);
+                return;
+            }
+        }
+    }
+
+    fn end_irrevocable(&self, context: &mut Self::Context<'_>) {
+        if context.irrevocable {
+            // Validated when waiting, after releasing the sequence
+            context.read_set.copy_in_place();
+
+            // Nothing was written, the snapshot is still current
+            context.irrevocable = false;
+            self.irrevocable.store(false, Ordering::Relaxed);
+            self.sequence.store(context.snapshot, Ordering::SeqCst);
+        }
+    }
+
+    fn wait(&self, context: &mut Self::Context<'_>, deadline: Option<Instant>) {
+        let waiter = Waiter::new();
+
+        if context.read_set.is_empty() {
+            // Nothing can wake us up, block until the deadline
+            waiter.wait(deadline);
+            return;
+        }
+
+        for var in context.read_set.iter_vars() {
+            var.meta.get_or_init().waiters.register(&waiter);
+        }
+
+        // Validate after registering,
+        // otherwise a commit between reading and registering would never wake us up
+        if self.validate(&context.read_set).is_ok() {
+            waiter.wait(deadline);
+        }
+
+        for var in context.read_set.iter_vars() {
+            var.meta.get_or_init().waiters.unregister(&waiter);
+        }
+    }
+
+    fn checkpoint(&self, context: &mut Self::Context<'_>) -> Self::Checkpoint {
+        context.write_set.checkpoint()
+    }
+
+    fn release(&self, context: &mut Self::Context<'_>, checkpoint: Self::Checkpoint) {
+        context.write_set.release(checkpoint)
+    }
+
+    fn rollback(&self, context: &mut Self::Context<'_>, checkpoint: Self::Checkpoint) {
+        // Keep the read set, the reads are still a part of the transaction
+        context.write_set.rollback(checkpoint)
+    }
+}
@@ -0,0 +1,135 @@
+use std::mem::MaybeUninit;
+
+use crate::{algorithm::any_var::AnyTVar, atomic_bytes, SafeRead, TVar, TVarId};
+
+#[cfg(feature = "small_alloc")]
+use smallvec::SmallVec;
+
+/// Value-based Read-Set
+/// Logs the bytes of values read, validation compares them with the TVars
+pub struct ReadSet<'var> {
+    #[cfg(not(feature = "small_alloc"))]
+    buffer: Vec<u8>,
+    #[cfg(not(feature = "small_alloc"))]
+    entries: Vec<Entry<'var>>,
+
+    #[cfg(feature = "small_alloc")]
+    buffer: SmallVec<[u8; 512]>,
+    #[cfg(feature = "small_alloc")]
+    entries: SmallVec<[Entry<'var>; 16]>,
+}
+
+// The offset of the values not copied yet
+const IN_PLACE: usize = usize::MAX;
+
+#[derive(Clone, Copy)]
+struct Entry<'var> {
+    // the var without generic T
+    var: AnyTVar<'var>,
+    // start byte index in buffer
+    offset: usize,
+    // the size of T
+    len: usize,
+}
+
+impl<'var> ReadSet<'var> {
+    pub fn new() -> Self {
+        ReadSet {
+            #[cfg(not(feature = "small_alloc"))]
+            buffer: Vec::new(),
+            #[cfg(not(feature = "small_alloc"))]
+            entries: Vec::new(),
+
+            #[cfg(feature = "small_alloc")]
+            buffer: SmallVec::new(),
+            #[cfg(feature = "small_alloc")]
+            entries: SmallVec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter_vars(&self) -> impl Iterator<Item = AnyTVar<'var>> + '_ {
+        self.entries.iter().map(|entry| entry.var)
+    }
+
+    /// Log the value read from var
+    /// The first read value is kept, the later ones must be the same if the read set is valid
+    pub fn log<T: SafeRead>(&mut self, var: &'var TVar<T>, value: &MaybeUninit<T>) {
+        let var = AnyTVar::from(var);
+
+        if self.entries.iter().any(|entry| entry.var == var) {
+            return;
+        }
+
+        let offset = self.buffer.len();
+        let len = std::mem::size_of::<T>();
+
+        // copy the bytes, the padding was frozen by the writers
+        let bytes = unsafe { std::slice::from_raw_parts(value.as_ptr() as *const u8, len) };
+        self.buffer.extend_from_slice(bytes);
+
+        self.entries.push(Entry { var, offset, len });
+    }
+
+    /// Log var without copying the value, while nobody else can write it
+    /// It must be copied by [`copy_in_place`](Self::copy_in_place) before anyone can
+    pub fn log_in_place<T: SafeRead>(&mut self, var: &'var TVar<T>) {
+        let var = AnyTVar::from(var);
+
+        if self.entries.iter().any(|entry| entry.var == var) {
+            return;
+        }
+
+        let len = std::mem::size_of::<T>();
+        self.entries.push(Entry {
+            var,
+            offset: IN_PLACE,
+            len,
+        });
+    }
+
+    /// Copy the values logged in place
+    pub fn copy_in_place(&mut self) {
+        let buffer = &mut self.buffer;
+
+        for entry in self.entries.iter_mut().filter(|entry| entry.offset == IN_PLACE) {
+            entry.offset = buffer.len();
+            buffer.resize(entry.offset + entry.len, 0);
+
+            // Safety: nobody else is writing it yet
+            unsafe {
+                atomic_bytes::load(
+                    entry.var.ptr as *const u8,
+                    buffer[entry.offset..].as_mut_ptr(),
+                    entry.len,
+                )
+            };
+        }
+    }
+
+    /// Check all TVars still hold the values read, return the changed one otherwise
+    pub fn validate(&self) -> Result<(), TVarId> {
+        for entry in &self.entries {
+            let logged = &self.buffer[entry.offset..entry.offset + entry.len];
+
+            // a writer may be storing the value
+            if !unsafe { atomic_bytes::equals(entry.var.ptr as *const u8, logged) } {
+                return Err(entry.var.id());
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.entries.clear();
+    }
+}
@@ -0,0 +1,333 @@
+use std::time::Instant;
+
+use crate::{
+    version::Version,
+    version_clock::{ClockStrategy, VersionClock},
+    CommitInfo, Contention, SafeRead, StmError, TVar,
+};
+
+use super::Algorithm;
+
+mod eager;
+mod read_set;
+mod readonly;
+mod write;
+
+/// TL2 (Transactional Locking II)
+///
+/// Every `TVar` has a versioned lock, the writes are buffered and locked at commit time.
+/// A transaction runs in a read-only context first (no read set is logged),
+/// it will be switched to a write context after it tried to write.
+///
+/// When a `TVar` newer than the snapshot is read, the write context tries to
+/// extend the snapshot (LSA style) by validating the read set again instead of aborting.
+/// A read-only context cannot do it without a read set,
+/// so it will be switched to a write context after such a failure.
+pub struct Tl2 {
+    global_version_clock: VersionClock,
+    eager: bool,
+    // Some in multi-version mode
+    history_depth: Option<usize>,
+    snapshot_isolation: bool,
+}
+
+impl Tl2 {
+    pub fn new() -> Self {
+        Tl2 {
+            global_version_clock: VersionClock::new(),
+            eager: false,
+            history_depth: None,
+            snapshot_isolation: false,
+        }
+    }
+
+    /// Use encounter-time locking (TinySTM style)
+    ///
+    /// A `TVar` is locked on the first write and written in place,
+    /// so a conflict aborts the transaction at once instead of at commit time.
+    /// It suits the long transactions writing a lot,
+    /// but a transaction blocks the readers of its written `TVar`s until it ends.
+    /// ```
+    /// # use xstm::{Stm, Tl2};
+    /// let stm = Stm::with_algorithm(Tl2::new().eager());
+    /// ```
+    pub fn eager(mut self) -> Self {
+        self.eager = true;
+        self
+    }
+
+    /// Keep the last `depth` old values of every written `TVar`
+    ///
+    /// A read-only transaction reads the old value valid at its snapshot
+    /// instead of aborting when the `TVar` was changed after the snapshot,
+    /// so it only aborts when more than `depth` transactions wrote the `TVar` meanwhile.
+    /// The older values are dropped when the new ones are committed.
+    /// ```
+    /// # use xstm::{Stm, Tl2};
+    /// let stm = Stm::with_algorithm(Tl2::new().multi_version(8));
+    /// ```
+    pub fn multi_version(mut self, depth: usize) -> Self {
+        self.history_depth = Some(depth);
+        self
+    }
+
+    /// Use snapshot isolation instead of serializability
+    ///
+    /// A transaction still reads a consistent snapshot,
+    /// but only aborts when a `TVar` it writes was written by another transaction after the snapshot.
+    /// The read set is not validated at commit time.
+    ///
+    /// **It's weaker than the default mode**: two transactions reading the same `TVar`s
+    /// and writing different ones can both commit (write skew),
+    /// e.g. both see `a + b >= 1` and set one of them to 0.
+    /// Write the `TVar`s the invariant depends on (even the same value)
+    /// to make such transactions conflict.
+    /// ```
+    /// # use xstm::{Stm, Tl2};
+    /// let stm = Stm::with_algorithm(Tl2::new().snapshot_isolation());
+    /// ```
+    pub fn snapshot_isolation(mut self) -> Self {
+        self.snapshot_isolation = true;
+        self
+    }
+
+    /// Choose how the global version clock is advanced,
+    /// [`ClockStrategy::Gv1`] by default
+    /// ```
+    /// # use xstm::{ClockStrategy, Stm, Tl2};
+    /// let stm = Stm::with_algorithm(Tl2::new().clock(ClockStrategy::Gv5));
+    /// ```
+    pub fn clock(mut self, strategy: ClockStrategy) -> Self {
+        self.global_version_clock = VersionClock::with_strategy(strategy);
+        self
+    }
+}
+
+impl Default for Tl2 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tl2 {
+    fn write_context(&self, read_version: Version) -> ContextInternal<'_> {
+        if self.eager {
+            ContextInternal::Eager(eager::Context::new(self, read_version))
+        } else {
+            ContextInternal::Write(write::Context::new(self, read_version))
+        }
+    }
+}
+
+// Hide the details for user
+enum ContextInternal<'var> {
+    ReadOnly(readonly::Context<'var>),
+    Write(write::Context<'var>),
+    Eager(eager::Context<'var>),
+}
+
+/// The transaction context of [`Tl2`]
+pub struct Context<'var> {
+    internal: ContextInternal<'var>,
+}
+
+/// The checkpoint of [`Tl2`]
+pub struct Checkpoint {
+    internal: CheckpointInternal,
+}
+
+enum CheckpointInternal {
+    // Read-only context has nothing to roll back
+    ReadOnly,
+    Write(write::Checkpoint),
+    Eager(eager::Checkpoint),
+}
+
+impl Algorithm for Tl2 {
+    type Context<'var> = Context<'var>;
+    type Checkpoint = Checkpoint;
+
+    fn new_context<'var>(&'var self) -> Self::Context<'var> {
+        let read_version = self.global_version_clock.sample();
+
+        Context {
+            internal: ContextInternal::ReadOnly(readonly::Context::new(self, read_version)),
+        }
+    }
+
+    fn reset<'var>(&'var self, context: &mut Self::Context<'var>) {
+        let clock = &self.global_version_clock;
+
+        match &mut context.internal {
+            ContextInternal::ReadOnly(readonly) => {
+                if readonly.needs_write_context() {
+                    // Convert it to write context
+                    context.internal = self.write_context(clock.sample_unlocked());
+                } else {
+                    // just reset the read_only context
+                    readonly.reset(clock.sample());
+                }
+            }
+            ContextInternal::Write(write) => {
+                // Nothing can be committed while an irrevocable transaction is running
+                write.reset(clock.sample_unlocked());
+            }
+            ContextInternal::Eager(eager) => {
+                eager.reset();
+            }
+        }
+    }
+
+    fn read<'var, T: SafeRead>(
+        &self,
+        context: &mut Self::Context<'var>,
+        var: &'var TVar<T>,
+    ) -> Result<T, StmError> {
+        match &mut context.internal {
+            ContextInternal::ReadOnly(context) => context.read(var),
+            ContextInternal::Write(context) => context.read(var),
+            ContextInternal::Eager(context) => context.read(var),
+        }
+    }
+
+    fn inspect<'var, T: SafeRead, R>(
+        &self,
+        context: &mut Self::Context<'var>,
+        var: &'var TVar<T>,
+        f: impl FnOnce(&T) -> R,
+    ) -> Result<R, StmError> {
+        match &mut context.internal {
+            ContextInternal::ReadOnly(context) => context.inspect(var, f),
+            ContextInternal::Write(context) => context.inspect(var, f),
+            ContextInternal::Eager(context) => context.inspect(var, f),
+        }
+    }
+
+    fn update<'var, T: SafeRead, R>(
+        &self,
+        context: &mut Self::Context<'var>,
+        var: &'var TVar<T>,
+        f: impl FnOnce(&mut T) -> R,
+        contention: &Contention,
+    ) -> Result<R, StmError> {
+        match &mut context.internal {
+            ContextInternal::ReadOnly(context) => context.update(var, f),
+            ContextInternal::Write(context) => context.update(var, f),
+            ContextInternal::Eager(context) => context.update(var, f, contention),
+        }
+    }
+
+    fn write<'var, T: SafeRead>(
+        &self,
+        context: &mut Self::Context<'var>,
+        var: &'var TVar<T>,
+        value: T,
+        contention: &Contention,
+    ) -> Result<(), StmError> {
+        match &mut context.internal {
+            ContextInternal::ReadOnly(context) => context.write(var, value),
+            ContextInternal::Write(context) => context.write(var, value),
+            ContextInternal::Eager(context) => context.write(var, value, contention),
+        }
+    }
+
+    fn commit(
+        &self,
+        context: &mut Self::Context<'_>,
+        contention: &Contention,
+    ) -> Result<CommitInfo, StmError> {
+        match &mut context.internal {
+            ContextInternal::ReadOnly(context) => context.try_commit(),
+            ContextInternal::Write(context) => context.try_commit(contention),
+            ContextInternal::Eager(context) => context.try_commit(),
+        }
+    }
+
+    fn switches_context(&self, context: &Self::Context<'_>) -> bool {
+        match &context.internal {
+            ContextInternal::ReadOnly(context) => context.needs_write_context(),
+            _ => false,
+        }
+    }
+
+    fn abort(&self, context: &mut Self::Context<'_>) {
+        if let ContextInternal::Eager(eager) = &mut context.internal {
+            eager.abort();
+        }
+    }
+
+    fn conflicted(&self, context: &Self::Context<'_>) -> bool {
+        match &context.internal {
+            // Writing in read-only context is not a conflict
+            ContextInternal::ReadOnly(context) => context.tried_extending(),
+            _ => true,
+        }
+    }
+
+    fn begin_irrevocable<'var>(&'var self, context: &mut Self::Context<'var>) {
+        self.global_version_clock.lock();
+
+        if let ContextInternal::ReadOnly(_) = context.internal {
+            // A write would switch the context and run the transaction again
+            context.internal = self.write_context(self.global_version_clock.sample());
+        }
+
+        match &mut context.internal {
+            ContextInternal::ReadOnly(_) => unreachable!(),
+            ContextInternal::Write(context) => context.set_irrevocable(true),
+            ContextInternal::Eager(context) => context.set_irrevocable(true),
+        }
+    }
+
+    fn end_irrevocable(&self, context: &mut Self::Context<'_>) {
+        match &mut context.internal {
+            ContextInternal::ReadOnly(_) => (),
+            ContextInternal::Write(context) => context.set_irrevocable(false),
+            ContextInternal::Eager(context) => context.set_irrevocable(false),
+        }
+
+        self.global_version_clock.unlock();
+    }
+
+    fn wait(&self, context: &mut Self::Context<'_>, deadline: Option<Instant>) {
+        match &mut context.internal {
+            ContextInternal::ReadOnly(context) => context.wait(),
+            ContextInternal::Write(context) => context.wait(deadline),
+            ContextInternal::Eager(context) => context.wait(deadline),
+        }
+    }
+
+    fn checkpoint(&self, context: &mut Self::Context<'_>) -> Self::Checkpoint {
+        let internal = match &mut context.internal {
+            ContextInternal::ReadOnly(_) => CheckpointInternal::ReadOnly,
+            ContextInternal::Write(context) => CheckpointInternal::Write(context.checkpoint()),
+            ContextInternal::Eager(context) => CheckpointInternal::Eager(context.checkpoint()),
+        };
+
+        Checkpoint { internal }
+    }
+
+    fn release(&self, context: &mut Self::Context<'_>, checkpoint: Self::Checkpoint) {
+        match (&mut context.internal, checkpoint.internal) {
+            (ContextInternal::Write(context), CheckpointInternal::Write(checkpoint)) => {
+                context.release(checkpoint)
+            }
+            (ContextInternal::Eager(context), CheckpointInternal::Eager(checkpoint)) => {
+                context.release(checkpoint)
+            }
+            _ => (),
+        }
+    }
+
+    fn rollback(&self, context: &mut Self::Context<'_>, checkpoint: Self::Checkpoint) {
+        match (&mut context.internal, checkpoint.internal) {
+            (ContextInternal::Write(context), CheckpointInternal::Write(checkpoint)) => {
+                context.rollback(checkpoint)
+            }
+            (ContextInternal::Eager(context), CheckpointInternal::Eager(checkpoint)) => {
+                context.rollback(checkpoint)
+            }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,358 @@
+use std::time::Instant;
+
+use crate::{
+    algorithm::any_var::AnyTVar,
+    version::Version,
+    version_clock::Tick,
+    versioned_lock::{self},
+    AbortReason, CommitInfo, Contention, SafeRead, StmError, TVar,
+};
+
+use super::{read_set::ReadSet, Tl2};
+use undo_log::UndoLog;
+
+mod undo_log;
+pub use undo_log::Checkpoint;
+
+struct LockedVar<'var> {
+    var: AnyTVar<'var>,
+    // the size of T
+    len: usize,
+    guard: versioned_lock::Guard<'var>,
+}
+
+// A write transaction context with encounter-time locking
+// TVars are locked on the first write and written in place,
+// the old values are kept in the undo log
+pub struct Context<'var> {
+    tl2: &'var Tl2,
+    read_version: Version,
+    locked_vars: Vec<LockedVar<'var>>,
+    undo_log: UndoLog<'var>,
+    read_set: ReadSet<'var>,
+    // no other transaction can commit
+    irrevocable: bool,
+}
+
+impl<'var> Context<'var> {
+    pub fn new(tl2: &'var Tl2, read_version: Version) -> Self {
+        Context {
+            tl2,
+            read_version,
+            locked_vars: Vec::new(),
+            undo_log: UndoLog::new(),
+            read_set: ReadSet::new(),
+            irrevocable: false,
+        }
+    }
+
+    pub fn set_irrevocable(&mut self, irrevocable: bool) {
+        self.irrevocable = irrevocable;
+    }
+
+    fn locked_by_self(&self, var: AnyTVar<'var>) -> bool {
+        self.locked_vars
+            .iter()
+            .any(|locked_var| locked_var.var == var)
+    }
+
+    pub fn read<T: SafeRead>(&mut self, var: &'var TVar<T>) -> Result<T, StmError> {
+        if self.locked_by_self(var.into()) {
+            // Nobody else can write it
+            // and it was not newer than read_version when we locked it
+            return Ok(unsafe { var.value_ptr().read() });
+        }
+
+        if self.irrevocable {
+            // The latest value stays valid until we commit
+            self.read_set.log(var);
+
+            return Ok(var.read_latest());
+        }
+
+        loop {
+            if let Some(value) = var.read_with_check(self.read_version) {
+                // Log it to read_set
+                self.read_set.log(var);
+
+                return Ok(value);
+            }
+
+            // The TVar is newer than the snapshot, try to extend it instead of aborting
+            self.read_version = self
+                .read_set
+                .extend(&self.tl2.global_version_clock, self.read_version, |read_entry| {
+                    self.locked_by_self(read_entry)
+                })
+                .map_err(|changed| {
+                    StmError::Retry(AbortReason::ReadValidation {
+                        var: changed.or(Some(var.id())),
+                    })
+                })?;
+        }
+    }
+
+    pub fn inspect<T: SafeRead, R>(
+        &mut self,
+        var: &'var TVar<T>,
+        f: impl FnOnce(&T) -> R,
+    ) -> Result<R, StmError> {
+        if self.locked_by_self(var.into()) {
+            // Nobody else can write it, run f in place
+            return Ok(f(unsafe { &*var.value_ptr() }));
+        }
+
+        if self.irrevocable {
+            // The latest value stays valid until we commit,
+            // but the other eager writers may still write it in place, copy it
+            self.read_set.log(var);
+
+            return Ok(var.inspect_latest(f));
+        }
+
+        let mut f = f;
+        loop {
+            match var.inspect_with_check(self.read_version, f) {
+                Ok(result) => {
+                    self.read_set.log(var);
+                    return Ok(result);
+                }
+                Err(back) => f = back,
+            }
+
+            // The TVar is newer than the snapshot, try to extend it instead of aborting
+            self.read_version = self
+                .read_set
+                .extend(&self.tl2.global_version_clock, self.read_version, |read_entry| {
+                    self.locked_by_self(read_entry)
+                })
+                .map_err(|changed| {
+                    StmError::Retry(AbortReason::ReadValidation {
+                        var: changed.or(Some(var.id())),
+                    })
+                })?;
+        }
+    }
+
+    pub fn write<T: SafeRead>(
+        &mut self,
+        var: &'var TVar<T>,
+        value: T,
+        contention: &Contention,
+    ) -> Result<(), StmError> {
+        self.lock_for_write(var, contention)?;
+
+        // write in place
+        // Safety: the TVar was locked by us
+        unsafe { var.write_in_place(value) };
+
+        Ok(())
+    }
+
+    pub fn update<T: SafeRead, R>(
+        &mut self,
+        var: &'var TVar<T>,
+        f: impl FnOnce(&mut T) -> R,
+        contention: &Contention,
+    ) -> Result<R, StmError> {
+        self.lock_for_write(var, contention)?;
+
+        // The readers may be copying it, so it's changed in a copy and stored back
+        // Safety: the TVar was locked by us
+        let mut value = unsafe { var.value_ptr().read() };
+        let result = f(&mut value);
+        unsafe { var.write_in_place(value) };
+
+        Ok(result)
+    }
+
+    // Lock the TVar and save its value for rolling back
+    fn lock_for_write<T: SafeRead>(
+        &mut self,
+        var: &'var TVar<T>,
+        contention: &Contention,
+    ) -> Result<(), StmError> {
+        if !self.locked_by_self(var.into()) {
+            if self.irrevocable {
+                // The holders will abort or finish committing
+                self.lock(var.into(), std::mem::size_of::<T>(), &Contention::irrevocable())?;
+            } else {
+                self.lock(var.into(), std::mem::size_of::<T>(), contention)?;
+            }
+        }
+
+        self.undo_log.log(var);
+
+        Ok(())
+    }
+
+    fn lock(
+        &mut self,
+        var: AnyTVar<'var>,
+        len: usize,
+        contention: &Contention,
+    ) -> Result<(), StmError> {
+        let mut spins = 0;
+
+        let guard = loop {
+            if let Some(guard) = var.lock.try_lock() {
+                contention.on_locked(var.id());
+                break guard;
+            }
+
+            if !contention.on_busy_lock(var.id(), spins) {
+                // locked by others, don't wait until commit to find it
+                return Err(StmError::Retry(AbortReason::LockContention {
+                    var: Some(var.id()),
+                }));
+            }
+
+            spins += 1;
+        };
+
+        // the version before locking
+        let version = -var.lock.version();
+
+        if self.tl2.history_depth.is_some() {
+            // Save the committed value before writing in place
+            // Safety: the TVar was locked by us
+            let data = unsafe { std::slice::from_raw_parts(var.ptr as *const u8, len) };
+            var.meta.get_or_init().history.seed(version, data);
+        }
+
+        // Keep it locked, it will be released with the others
+        self.locked_vars.push(LockedVar { var, len, guard });
+
+        // The irrevocable transaction reads the latest values, whatever their versions are
+        while !self.irrevocable && version > self.read_version {
+            // The TVar is newer than the snapshot, try to extend it
+            // otherwise the transaction has been doomed
+            self.read_version = self
+                .read_set
+                .extend(&self.tl2.global_version_clock, self.read_version, |read_entry| {
+                    self.locked_by_self(read_entry)
+                })
+                .map_err(|changed| {
+                    StmError::Retry(AbortReason::ReadValidation {
+                        var: changed.or(Some(var.id())),
+                    })
+                })?;
+        }
+
+        Ok(())
+    }
+
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        self.undo_log.checkpoint()
+    }
+
+    pub fn release(&mut self, checkpoint: Checkpoint) {
+        self.undo_log.release(checkpoint)
+    }
+
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        // Keep the locks and the read set, they are still a part of the transaction
+        // Safety: the TVars in undo log are still locked
+        unsafe { self.undo_log.rollback(checkpoint) }
+    }
+
+    /// Restore the written TVars and unlock them
+    pub fn abort(&mut self) {
+        if self.locked_vars.is_empty() {
+            return;
+        }
+
+        // Safety: the TVars in undo log are still locked
+        unsafe { self.undo_log.rollback_all() }
+
+        // The other transactions may have read the values written by us
+        // between their pre- and post-validation,
+        // give the TVars a new version to make them fail
+        let version = self.tl2.global_version_clock.advance();
+        for mut locked_var in self.locked_vars.drain(..) {
+            locked_var.guard.set_version(version);
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.abort();
+
+        // sample after aborting, the unlocked TVars got a new version
+        // Nothing can be committed while an irrevocable transaction is running
+        self.read_version = self.tl2.global_version_clock.sample_unlocked();
+        self.read_set.clear();
+    }
+
+    pub fn try_commit(&mut self) -> Result<CommitInfo, StmError> {
+        if self.locked_vars.is_empty() {
+            // All reads were validated, nothing to write
+            return Ok(CommitInfo::new(self.read_set.len(), 0, None));
+        }
+
+        let clock = &self.tl2.global_version_clock;
+
+        // tick the global version clock
+        // Nobody committed since the reads of an irrevocable transaction
+        let tick = if self.irrevocable {
+            Tick {
+                write_version: clock.tick_irrevocable(),
+                exclusive: true,
+            }
+        } else {
+            clock
+                .tick(self.read_version)
+                .ok_or(StmError::Retry(AbortReason::Irrevocable))?
+        };
+        let write_version = tick.write_version;
+
+        // when wv = rv + 1
+        // Don't need to validate
+        // The write-write conflicts were checked when locking,
+        // nothing else to validate in snapshot isolation
+        if !tick.exclusive && !self.tl2.snapshot_isolation {
+            // validate the read set
+            self.read_set
+                .validate(self.read_version, |read_entry| self.locked_by_self(read_entry))
+                .map_err(|var| StmError::Retry(AbortReason::CommitValidation { var: Some(var) }))?;
+        }
+
+        let commit = CommitInfo::new(
+            self.read_set.len(),
+            self.locked_vars.len(),
+            Some(isize::from(write_version) as u64),
+        );
+
+        // The data was written already, just publish the version
+        for LockedVar { var, len, mut guard } in self.locked_vars.drain(..) {
+            if let Some(depth) = self.tl2.history_depth {
+                // Safety: the TVar was locked by us
+                let data = unsafe { std::slice::from_raw_parts(var.ptr as *const u8, len) };
+                var.meta.get_or_init().history.push(write_version, data, depth);
+            }
+
+            guard.set_version(write_version);
+            drop(guard);
+
+            // wake up the transactions blocked on this TVar
+            var.meta.notify_waiters();
+        }
+
+        self.undo_log.clear();
+
+        Ok(commit)
+    }
+
+    pub fn wait(&mut self, deadline: Option<Instant>) {
+        // Don't block the other transactions while waiting
+        self.abort();
+
+        self.read_set.wait(self.read_version, deadline)
+    }
+}
+
+impl<'var> Drop for Context<'var> {
+    fn drop(&mut self) {
+        // The transaction was aborted by user
+        self.abort();
+    }
+}
@@ -0,0 +1,119 @@
+use crate::{algorithm::any_var::AnyTVar, atomic_bytes, SafeRead, TVar};
+
+/// The old values of the TVars written in place
+pub struct UndoLog<'var> {
+    entries: Vec<Entry<'var>>,
+    buffer: Vec<u8>,
+    // Entries before this index were created before the latest checkpoint
+    undo_below: usize,
+}
+
+#[derive(Clone, Copy)]
+struct Entry<'var> {
+    var: AnyTVar<'var>,
+    // start byte index in buffer
+    offset: usize,
+    // the size of T
+    len: usize,
+}
+
+/// The state of undo log that can be rolled back to
+#[derive(Clone, Copy)]
+pub struct Checkpoint {
+    entries_len: usize,
+    buffer_len: usize,
+    // undo_below of the outer checkpoint
+    undo_below: usize,
+}
+
+impl<'var> Entry<'var> {
+    // Copy the old value back to TVar
+    // Safety: the TVar was locked by us
+    unsafe fn restore(&self, buffer: &[u8]) {
+        let saved = &buffer[self.offset..self.offset + self.len];
+
+        atomic_bytes::store(saved.as_ptr(), self.var.ptr as *mut u8, self.len);
+    }
+}
+
+impl<'var> UndoLog<'var> {
+    pub fn new() -> Self {
+        UndoLog {
+            entries: Vec::new(),
+            buffer: Vec::new(),
+            undo_below: 0,
+        }
+    }
+
+    /// Save the current value of var before it is overwritten
+    pub fn log<T: SafeRead>(&mut self, var: &'var TVar<T>) {
+        let var = AnyTVar::from(var);
+
+        // Only the first write since the latest checkpoint need to be saved
+        if self.entries[self.undo_below..]
+            .iter()
+            .any(|entry| entry.var == var)
+        {
+            return;
+        }
+
+        let offset = self.buffer.len();
+        let len = std::mem::size_of::<T>();
+
+        // Safety: the TVar was locked by us, nobody is writing it
+        let current = unsafe { std::slice::from_raw_parts(var.ptr as *const u8, len) };
+        self.buffer.extend_from_slice(current);
+
+        self.entries.push(Entry { var, offset, len });
+    }
+
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        let checkpoint = Checkpoint {
+            entries_len: self.entries.len(),
+            buffer_len: self.buffer.len(),
+            undo_below: self.undo_below,
+        };
+
+        self.undo_below = self.entries.len();
+
+        checkpoint
+    }
+
+    /// Keep the writes after checkpoint
+    pub fn release(&mut self, checkpoint: Checkpoint) {
+        self.undo_below = checkpoint.undo_below;
+    }
+
+    /// Restore the values of TVars to the checkpoint
+    ///
+    /// # Safety
+    /// All logged TVars must still be locked by us
+    pub unsafe fn rollback(&mut self, checkpoint: Checkpoint) {
+        // the latest first
+        for entry in self.entries.drain(checkpoint.entries_len..).rev() {
+            entry.restore(&self.buffer);
+        }
+        self.buffer.truncate(checkpoint.buffer_len);
+
+        self.undo_below = checkpoint.undo_below;
+    }
+
+    /// Restore all values of TVars written in the transaction
+    ///
+    /// # Safety
+    /// All logged TVars must still be locked by us
+    pub unsafe fn rollback_all(&mut self) {
+        self.rollback(Checkpoint {
+            entries_len: 0,
+            buffer_len: 0,
+            undo_below: 0,
+        })
+    }
+
+    /// Forget all saved values, the writes are committed
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.buffer.clear();
+        self.undo_below = 0;
+    }
+}
@@ -0,0 +1,146 @@
+use std::time::Instant;
+
+use crate::algorithm::any_var::AnyTVar;
+use crate::{version::Version, version_clock::VersionClock, waiter::Waiter, SafeRead, TVar, TVarId};
+
+#[cfg(feature = "small_alloc")]
+use smallvec::SmallVec;
+
+pub struct ReadSet<'var> {
+    #[cfg(not(feature = "small_alloc"))]
+    entries: Vec<Entry<'var>>,
+    
+    #[cfg(feature = "small_alloc")]
+    entries: SmallVec<[Entry<'var>; 16]>,
+}
+
+pub type Entry<'var> = AnyTVar<'var>;
+
+impl<'var> ReadSet<'var> {
+    pub fn new() -> Self {
+        ReadSet {
+            #[cfg(not(feature = "small_alloc"))]
+            entries: Vec::with_capacity(16),
+
+            #[cfg(feature = "small_alloc")]
+            entries: SmallVec::new()
+        }
+    }
+
+    pub fn iter_vars(&self) -> impl Iterator<Item = Entry<'var>> + '_ {
+        self.entries.iter().copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn get_entry<Var: Into<AnyTVar<'var>>>(&self, var: Var) -> Option<Entry<'var>> {
+        let var = var.into();
+        self.entries
+            .iter()
+            .find(|entry| *entry == &var)
+            .copied()
+    }
+
+    /// Log an read entry
+    pub fn log<T: SafeRead>(&mut self, var: &'var TVar<T>) {
+        if self.get_entry(var).is_none() {
+            // create entry
+            self.entries.push(var.into());
+        }
+    }
+
+    /// Check all read TVars were not changed since read_version,
+    /// return the changed one otherwise
+    /// `locked_by_self` tells the TVars locked by the committing transaction itself
+    pub fn validate(
+        &self,
+        read_version: Version,
+        locked_by_self: impl Fn(AnyTVar<'var>) -> bool,
+    ) -> Result<(), TVarId> {
+        for read_entry in self.iter_vars() {
+            let mut version = read_entry.lock.version();
+
+            if version.is_locked() {
+                // check it was locked by ourselves
+                if !locked_by_self(read_entry) {
+                    // locked by others
+                    return Err(read_entry.id());
+                }
+
+                // locked by self
+                // make it positive for compare with read_version
+                version = -version
+            }
+
+            // check the version
+            if version > read_version {
+                return Err(read_entry.id());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Try to move the snapshot of the transaction to the current clock
+    ///
+    /// It's possible when none of the read TVars was changed since read_version,
+    /// return the new read version, or the changed TVar if it's known
+    pub fn extend(
+        &self,
+        clock: &VersionClock,
+        read_version: Version,
+        locked_by_self: impl Fn(AnyTVar<'var>) -> bool,
+    ) -> Result<Version, Option<TVarId>> {
+        // sample before validating,
+        // the TVars changed after it will be found by the later reads
+        let new_read_version = clock.sample_newer(read_version);
+
+        if new_read_version == read_version {
+            // Nobody committed, the read failed because of a lock
+            return Err(None);
+        }
+
+        self.validate(read_version, locked_by_self).map_err(Some)?;
+
+        Ok(new_read_version)
+    }
+
+    /// Block until one of the read TVars was changed since read_version
+    pub fn wait(&self, read_version: Version, deadline: Option<Instant>) {
+        let waiter = Waiter::new();
+
+        if self.is_empty() {
+            // Nothing can wake us up, block until the deadline
+            waiter.wait(deadline);
+            return;
+        }
+
+        for read_entry in self.iter_vars() {
+            read_entry.meta.get_or_init().waiters.register(&waiter);
+        }
+
+        // Validate after registering,
+        // otherwise a commit between reading and registering would never wake us up
+        let changed = self
+            .iter_vars()
+            .any(|read_entry| !read_entry.lock.version().check(read_version));
+
+        if !changed {
+            waiter.wait(deadline);
+        }
+
+        for read_entry in self.iter_vars() {
+            read_entry.meta.get_or_init().waiters.unregister(&waiter);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{version::Version, AbortReason, CommitInfo, SafeRead, StmError, TVar};
+
+use super::Tl2;
+
+/// A read-only transaction context
+/// (Don't log any read or write set)
+pub struct Context<'var> {
+    tl2: &'var Tl2,
+    // Indicate the context tried perform a write operation
+    tried_writing: bool,
+    // Indicate the context tried to wait for the read set
+    tried_waiting: bool,
+    // Indicate the context read a TVar newer than read_version
+    tried_extending: bool,
+    read_version: Version,
+    // the number of reads, there's no read set
+    reads: usize,
+}
+
+impl<'var> Context<'var> {
+    pub fn new(tl2: &'var Tl2, read_version: Version) -> Self {
+        Context {
+            tl2,
+            tried_writing: false,
+            tried_waiting: false,
+            tried_extending: false,
+            read_version,
+            reads: 0,
+        }
+    }
+
+    pub fn read<T: SafeRead>(&mut self, var: &'var TVar<T>) -> Result<T, StmError> {
+        self.reads += 1;
+
+        let mut value = var.read_with_check(self.read_version);
+
+        if value.is_none() && self.tl2.history_depth.is_some() {
+            // It was changed after the snapshot, read the old value
+            value = var.read_from_history(self.read_version, &self.tl2.global_version_clock);
+        }
+
+        // The snapshot cannot be extended without read set
+        // Just set the flag, the transaction will be run in write context
+        self.tried_extending |= value.is_none();
+
+        value.ok_or(StmError::Retry(AbortReason::ReadValidation {
+            var: Some(var.id()),
+        }))
+    }
+
+    pub fn inspect<T: SafeRead, R>(
+        &mut self,
+        var: &'var TVar<T>,
+        f: impl FnOnce(&T) -> R,
+    ) -> Result<R, StmError> {
+        self.reads += 1;
+
+        let f = match var.inspect_with_check(self.read_version, f) {
+            Ok(result) => return Ok(result),
+            Err(f) => f,
+        };
+
+        if self.tl2.history_depth.is_some() {
+            // It was changed after the snapshot, run f on a copy of the old value
+            let clock = &self.tl2.global_version_clock;
+            if let Some(value) = var.read_from_history(self.read_version, clock) {
+                return Ok(f(&value));
+            }
+        }
+
+        self.tried_extending = true;
+
+        Err(StmError::Retry(AbortReason::ReadValidation {
+            var: Some(var.id()),
+        }))
+    }
+
+    pub fn write<T: SafeRead>(&mut self, _: &'var TVar<T>, _: T) -> Result<(), StmError> {
+        // Cannot perform a write operation
+        // Just set the flag an return
+
+        self.tried_writing = true;
+
+        Err(StmError::Retry(AbortReason::WriteInReadOnly))
+    }
+
+    pub fn update<T: SafeRead, R>(
+        &mut self,
+        _: &'var TVar<T>,
+        _: impl FnOnce(&mut T) -> R,
+    ) -> Result<R, StmError> {
+        // A write too
+        self.tried_writing = true;
+
+        Err(StmError::Retry(AbortReason::WriteInReadOnly))
+    }
+
+    pub fn tried_extending(&self) -> bool {
+        self.tried_extending
+    }
+
+    /// Tried to write, wait or extend the snapshot,
+    /// the next attempt must run in write context
+    pub fn needs_write_context(&self) -> bool {
+        self.tried_writing || self.tried_waiting || self.tried_extending
+    }
+
+    pub fn wait(&mut self) {
+        // The read set was not logged, nothing to wait for
+        // Just set the flag, the transaction will be run in write context
+        self.tried_waiting = true;
+    }
+
+    pub fn reset(&mut self, read_version: Version) {
+        self.read_version = read_version;
+        self.reads = 0;
+    }
+
+    pub fn try_commit(&mut self) -> Result<CommitInfo, StmError> {
+        // Committing a read-only transaction is always successful
+        Ok(CommitInfo::new(self.reads, 0, None))
+    }
+}
@@ -0,0 +1,278 @@
+use std::time::Instant;
+
+use crate::{version::Version, AbortReason, CommitInfo, Contention, SafeRead, StmError, TVar};
+
+use super::{read_set::ReadSet, Tl2};
+use crate::algorithm::write_set::WriteSet;
+
+pub use crate::algorithm::write_set::Checkpoint;
+
+// A write transaction context
+// will log read and write set
+pub struct Context<'var> {
+    tl2: &'var Tl2,
+    read_version: Version,
+    write_set: WriteSet<'var>,
+    read_set: ReadSet<'var>,
+    // no other transaction can commit
+    irrevocable: bool,
+}
+
+impl<'var> Context<'var> {
+    pub fn new(tl2: &'var Tl2, read_version: Version) -> Self {
+        Context {
+            tl2,
+            read_version,
+            write_set: WriteSet::new(),
+            read_set: ReadSet::new(),
+            irrevocable: false,
+        }
+    }
+
+    pub fn set_irrevocable(&mut self, irrevocable: bool) {
+        self.irrevocable = irrevocable;
+    }
+
+    pub fn read<T: SafeRead>(&mut self, var: &'var TVar<T>) -> Result<T, StmError> {
+        // Check we wrote before
+        if let Some(wrote_value) = self.write_set.try_read(var) {
+            // Log it to read_set
+            self.read_set.log(var);
+
+            return Ok(wrote_value);
+        }
+
+        if self.irrevocable {
+            // The latest value stays valid until we commit
+            self.read_set.log(var);
+
+            return Ok(var.read_latest());
+        }
+
+        loop {
+            // read from TVar
+            if let Some(value) = var.read_with_check(self.read_version) {
+                // Log it to read_set
+                self.read_set.log(var);
+
+                return Ok(value);
+            }
+
+            // The TVar is newer than the snapshot, try to extend it instead of aborting
+            // Nothing was locked by us before committing
+            self.read_version = self
+                .read_set
+                .extend(&self.tl2.global_version_clock, self.read_version, |_| false)
+                .map_err(|changed| {
+                    StmError::Retry(AbortReason::ReadValidation {
+                        var: changed.or(Some(var.id())),
+                    })
+                })?;
+        }
+    }
+
+    pub fn inspect<T: SafeRead, R>(
+        &mut self,
+        var: &'var TVar<T>,
+        f: impl FnOnce(&T) -> R,
+    ) -> Result<R, StmError> {
+        // Check we wrote before
+        let mut f = match self.write_set.try_inspect(var, f) {
+            Ok(result) => {
+                self.read_set.log(var);
+                return Ok(result);
+            }
+            Err(f) => f,
+        };
+
+        if self.irrevocable {
+            // The lazy writers can't tick the clock until we commit,
+            // so nobody writes it once unlocked, run f in place
+            self.read_set.log(var);
+
+            return Ok(unsafe { var.inspect_in_place(f) });
+        }
+
+        loop {
+            match var.inspect_with_check(self.read_version, f) {
+                Ok(result) => {
+                    self.read_set.log(var);
+                    return Ok(result);
+                }
+                Err(back) => f = back,
+            }
+
+            // The TVar is newer than the snapshot, try to extend it instead of aborting
+            self.read_version = self
+                .read_set
+                .extend(&self.tl2.global_version_clock, self.read_version, |_| false)
+                .map_err(|changed| {
+                    StmError::Retry(AbortReason::ReadValidation {
+                        var: changed.or(Some(var.id())),
+                    })
+                })?;
+        }
+    }
+
+    pub fn update<T: SafeRead, R>(
+        &mut self,
+        var: &'var TVar<T>,
+        f: impl FnOnce(&mut T) -> R,
+    ) -> Result<R, StmError> {
+        let tl2 = self.tl2;
+        let irrevocable = self.irrevocable;
+        let read_set = &mut self.read_set;
+        let read_version = &mut self.read_version;
+
+        // Copy the current value to write set once, it's changed there later
+        let fill = |dst: *mut T| {
+            if irrevocable {
+                // The latest value stays valid until we commit
+                unsafe { var.read_latest_into(dst) };
+                return Ok(());
+            }
+
+            while !unsafe { var.read_into_with_check(*read_version, dst) } {
+                // The TVar is newer than the snapshot, try to extend it instead of aborting
+                *read_version = read_set
+                    .extend(&tl2.global_version_clock, *read_version, |_| false)
+                    .map_err(|changed| {
+                        StmError::Retry(AbortReason::ReadValidation {
+                            var: changed.or(Some(var.id())),
+                        })
+                    })?;
+            }
+
+            Ok(())
+        };
+
+        let result = self.write_set.update(var, fill, f)?;
+        self.read_set.log(var);
+
+        Ok(result)
+    }
+
+    pub fn write<T: SafeRead>(&mut self, var: &'var TVar<T>, value: T) -> Result<(), StmError> {
+        // log it to write_set
+        self.write_set.log(var, value);
+
+        Ok(())
+    }
+
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        self.write_set.checkpoint()
+    }
+
+    pub fn release(&mut self, checkpoint: Checkpoint) {
+        self.write_set.release(checkpoint)
+    }
+
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        // Keep the read set, the reads are still a part of the transaction
+        self.write_set.rollback(checkpoint)
+    }
+
+    pub fn reset(&mut self, read_version: Version) {
+        self.read_version = read_version;
+        self.write_set.clear();
+        self.read_set.clear();
+    }
+
+    pub fn try_commit(&mut self, contention: &Contention) -> Result<CommitInfo, StmError> {
+        if self.write_set.is_empty() {
+            // All reads were validated at read_version, nothing to write
+            return Ok(CommitInfo::new(self.read_set.len(), 0, None));
+        }
+
+        if self.irrevocable {
+            return self.commit_irrevocable();
+        }
+
+        // try get lock write set
+
+        let mut guard = self.write_set
+            .try_lock(contention)
+            .map_err(|var| StmError::Retry(AbortReason::LockContention { var: Some(var) }))?;
+            
+        // tick the global version clock
+        let tick = self
+            .tl2
+            .global_version_clock
+            .tick(self.read_version)
+            .ok_or(StmError::Retry(AbortReason::Irrevocable))?;
+        let write_version = tick.write_version;
+
+        // when wv = rv + 1
+        // Don't need to validate
+        if !tick.exclusive {
+            if self.tl2.snapshot_isolation {
+                // Only the write-write conflicts matter
+                if let Err(var) = guard.validate(self.read_version) {
+                    // The lazy clocks are only advanced by the aborted transactions,
+                    // the next attempt wouldn't see the new version otherwise
+                    self.tl2.global_version_clock.sample_newer(self.read_version);
+
+                    return Err(StmError::Retry(AbortReason::CommitValidation {
+                        var: Some(var),
+                    }));
+                }
+            } else {
+                // validate the read set
+                self.read_set
+                    .validate(self.read_version, |read_entry| {
+                        guard
+                            .iter_vars()
+                            .any(|write_entry| read_entry == write_entry)
+                    })
+                    .map_err(|var| {
+                        StmError::Retry(AbortReason::CommitValidation { var: Some(var) })
+                    })?;
+            }
+        }
+
+        if let Some(depth) = self.tl2.history_depth {
+            guard.record_history(write_version, depth);
+        }
+
+        // Write the data and the version
+        guard.set_version(write_version);
+
+        guard.write_data_from_buffer();
+
+        // guard dropped here
+
+        Ok(self.commit_info(write_version))
+    }
+
+    fn commit_info(&self, write_version: Version) -> CommitInfo {
+        let write_version = isize::from(write_version) as u64;
+        CommitInfo::new(self.read_set.len(), self.write_set.len(), Some(write_version))
+    }
+
+    fn commit_irrevocable(&mut self) -> Result<CommitInfo, StmError> {
+        // Wait for the transactions which will abort or finish committing
+        let mut guard = self
+            .write_set
+            .try_lock(&Contention::irrevocable())
+            .map_err(|var| StmError::Retry(AbortReason::LockContention { var: Some(var) }))?;
+
+        // Nobody committed since the reads, nothing to validate
+        let write_version = self.tl2.global_version_clock.tick_irrevocable();
+
+        if let Some(depth) = self.tl2.history_depth {
+            guard.record_history(write_version, depth);
+        }
+
+        guard.set_version(write_version);
+
+        guard.write_data_from_buffer();
+
+        drop(guard);
+
+        Ok(self.commit_info(write_version))
+    }
+
+    pub fn wait(&mut self, deadline: Option<Instant>) {
+        self.read_set.wait(self.read_version, deadline)
+    }
+}
@@ -0,0 +1,419 @@
+use crate::{
+    atomic_bytes,
+    version::Version,
+    versioned_lock::{self},
+    Contention, SafeRead, TVar, TVarId,
+};
+
+use super::any_var::AnyTVar;
+
+mod buffer;
+use buffer::Buffer;
+
+#[cfg(feature = "small_alloc")]
+use smallvec::SmallVec;
+
+/// Write-Set
+pub struct WriteSet<'var> {
+    buffer: Buffer,
+
+    #[cfg(not(feature = "small_alloc"))]
+    entries: Vec<Entry<'var>>,
+    #[cfg(feature = "small_alloc")]
+    entries: SmallVec<[Entry<'var>; 16]>,
+
+    // Old values overwritten after a checkpoint
+    undo_entries: Vec<UndoEntry>,
+    undo_buffer: Vec<u8>,
+    // Entries before this offset were created before the latest checkpoint
+    // Overwriting them need to save the old value
+    undo_below: usize,
+}
+
+#[derive(Clone, Copy)]
+struct Entry<'var> {
+    // the var without generic T
+    var: AnyTVar<'var>,
+    // start byte index in buffer
+    offset: usize,
+    // the size of T
+    len: usize,
+}
+
+
+#[derive(Clone, Copy)]
+struct UndoEntry {
+    // start byte index in buffer
+    offset: usize,
+    // start byte index in undo buffer
+    undo_offset: usize,
+    // the size of T
+    len: usize,
+}
+
+/// The state of write-set that can be rolled back to
+#[derive(Clone, Copy)]
+pub struct Checkpoint {
+    buffer_len: usize,
+    entries_len: usize,
+    undo_entries_len: usize,
+    undo_buffer_len: usize,
+    // undo_below of the outer checkpoint
+    undo_below: usize,
+}
+
+impl<'var> Entry<'var> {
+    // Can only used for write entry
+    fn get_ptr_from_buffer(&self, buffer: &[u8]) -> *const u8 {
+        let begin = self.offset;
+        let end = begin + self.len;
+        let slice = &buffer[begin..end];
+        slice.as_ptr()
+    }
+
+    // Can only used for write entry
+    fn get_mut_ptr_from_buffer(&self, buffer: &mut [u8]) -> *mut u8 {
+        let begin = self.offset;
+        let end = begin + self.len;
+        let slice = &mut buffer[begin..end];
+        slice.as_mut_ptr()
+    }
+
+    // Copy the data in buffer to TVar
+    // Safety: no other transaction is writing the TVar
+    unsafe fn write_data_from_buffer(&self, buffer: &[u8]) {
+        let buffer_ptr = self.get_ptr_from_buffer(buffer);
+
+        let cell_ptr = self.var.ptr as *mut u8;
+
+        atomic_bytes::store(buffer_ptr, cell_ptr, self.len);
+    }
+}
+
+impl<'var> WriteSet<'var> {
+    pub fn new() -> WriteSet<'var> {
+        WriteSet {
+            buffer: Buffer::new(),
+
+            #[cfg(not(feature = "small_alloc"))]
+            entries: Vec::with_capacity(16),
+            #[cfg(feature = "small_alloc")]
+            entries: SmallVec::new(),
+
+            undo_entries: Vec::new(),
+            undo_buffer: Vec::new(),
+            undo_below: 0,
+        }
+    }
+
+    fn get_entry<T: SafeRead>(&self, var: &'var TVar<T>) -> Option<Entry<'var>> {
+        self.entries
+            .iter()
+            .find(|entry| entry.var == var.into())
+            .copied()
+    }
+
+    fn get_or_create_entry<T: SafeRead>(&mut self, var: &'var TVar<T>) -> Entry<'var> {
+        if let Some(entry) = self.get_entry(var) {
+            entry
+        } else {
+            // create a new write entry
+            const {
+                assert!(
+                    std::mem::align_of::<T>() <= buffer::ALIGN,
+                    "the TVar values can't be aligned more than 64 bytes"
+                )
+            };
+
+            // allocate buffer, aligned for T
+            let len = std::mem::size_of::<T>();
+            let offset = self.buffer.push(len, std::mem::align_of::<T>());
+
+            // create entry
+            self.entries.push(Entry {
+                var: var.into(),
+                offset,
+                len,
+            });
+            *self.entries.last().unwrap_or_else(|| unreachable!())
+        }
+    }
+
+    /// log an write entry
+    pub fn log<T: SafeRead>(&mut self, var: &'var TVar<T>, value: T) {
+        // Get or create entry
+        let write_entry = self.get_or_create_entry(var);
+
+        if write_entry.offset < self.undo_below {
+            // Created before the checkpoint, save the old value for rolling back
+            self.save_undo(write_entry);
+        }
+
+        // Copy the data to buffer
+        let ptr = write_entry.get_mut_ptr_from_buffer(&mut self.buffer) as *mut T;
+        // write to buffer, the typed write may leave the padding uninitialized
+        unsafe {
+            ptr.write(value);
+            atomic_bytes::freeze(ptr as *mut u8, std::mem::size_of::<T>());
+        }
+    }
+
+    /// Run `f` on the value in buffer to change it
+    ///
+    /// If the TVar was not written, `fill` copies its current value to a new entry,
+    /// the entry is dropped if `fill` fails
+    pub fn update<T: SafeRead, R, E>(
+        &mut self,
+        var: &'var TVar<T>,
+        fill: impl FnOnce(*mut T) -> Result<(), E>,
+        f: impl FnOnce(&mut T) -> R,
+    ) -> Result<R, E> {
+        let entry = match self.get_entry(var) {
+            Some(entry) => {
+                if entry.offset < self.undo_below {
+                    // Created before the checkpoint, save the old value for rolling back
+                    self.save_undo(entry);
+                }
+                entry
+            }
+            None => {
+                let entry = self.get_or_create_entry(var);
+                let ptr = entry.get_mut_ptr_from_buffer(&mut self.buffer) as *mut T;
+
+                if let Err(err) = fill(ptr) {
+                    self.entries.pop();
+                    self.buffer.truncate(entry.offset);
+                    return Err(err);
+                }
+                entry
+            }
+        };
+
+        let ptr = entry.get_mut_ptr_from_buffer(&mut self.buffer) as *mut T;
+
+        // Safety: the buffer is aligned for T
+        let result = f(unsafe { &mut *ptr });
+        // f may have assigned the whole value, uninitializing the padding
+        unsafe { atomic_bytes::freeze(ptr as *mut u8, std::mem::size_of::<T>()) };
+
+        Ok(result)
+    }
+
+    /// read value from logs
+    pub fn try_read<T: SafeRead>(&self, var: &'var TVar<T>) -> Option<T> {
+        let entry = self.get_entry(var)?;
+
+        // Read value from write entry
+        let ptr = entry.get_ptr_from_buffer(&self.buffer) as *const T;
+
+        Some(unsafe { ptr.read() })
+    }
+
+    /// Run `f` on the value in buffer, give `f` back if the TVar was not written
+    pub fn try_inspect<T: SafeRead, R, F: FnOnce(&T) -> R>(
+        &self,
+        var: &'var TVar<T>,
+        f: F,
+    ) -> Result<R, F> {
+        let Some(entry) = self.get_entry(var) else {
+            return Err(f);
+        };
+
+        let ptr = entry.get_ptr_from_buffer(&self.buffer) as *const T;
+
+        // Safety: the buffer is aligned for T
+        Ok(f(unsafe { &*ptr }))
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Copy all data in buffer to TVars without locking them
+    ///
+    /// # Safety
+    /// No other transaction can be writing the TVars at the same time
+    /// (e.g. the writer holds a global lock)
+    pub unsafe fn write_data_from_buffer(&self) {
+        for entry in self.entries.iter() {
+            entry.write_data_from_buffer(&self.buffer);
+        }
+    }
+
+    /// Wake up the transactions blocked on the written TVars
+    pub fn notify_waiters(&self) {
+        for entry in self.entries.iter() {
+            entry.var.meta.notify_waiters();
+        }
+    }
+
+    /// Try to lock all write entries
+    /// Ask `contention` before waiting for a busy lock
+    /// Lock all TVars, return the busy one if it fails
+    pub fn try_lock(&self, contention: &Contention) -> Result<Guard<'_, 'var>, TVarId> {
+        #[cfg(not(feature = "small_alloc"))]
+        let mut guards = Vec::with_capacity(self.entries.len());
+
+        #[cfg(feature = "small_alloc")]
+        let mut guards = SmallVec::<[_; 16]>::new();
+
+        for entry in self.entries.iter() {
+            let mut spins = 0;
+
+            let guard = loop {
+                if let Some(guard) = entry.var.lock.try_lock() {
+                    contention.on_locked(entry.var.id());
+                    break guard;
+                }
+
+                if !contention.on_busy_lock(entry.var.id(), spins) {
+                    return Err(entry.var.id());
+                }
+
+                spins += 1;
+            };
+
+            guards.push(GuardedEntry {
+                guard,
+                entry: *entry,
+            });
+        }
+
+        Ok(Guard { guards, buffer: &self.buffer })
+    }
+
+    fn save_undo(&mut self, entry: Entry<'var>) {
+        let undo_offset = self.undo_buffer.len();
+        let begin = entry.offset;
+        let end = begin + entry.len;
+
+        self.undo_buffer.extend_from_slice(&self.buffer[begin..end]);
+        self.undo_entries.push(UndoEntry {
+            offset: entry.offset,
+            undo_offset,
+            len: entry.len,
+        });
+    }
+
+    /// Save current state, the writes after it can be rolled back
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        let checkpoint = Checkpoint {
+            buffer_len: self.buffer.len(),
+            entries_len: self.entries.len(),
+            undo_entries_len: self.undo_entries.len(),
+            undo_buffer_len: self.undo_buffer.len(),
+            undo_below: self.undo_below,
+        };
+
+        self.undo_below = self.buffer.len();
+
+        checkpoint
+    }
+
+    /// Keep the writes after checkpoint
+    pub fn release(&mut self, checkpoint: Checkpoint) {
+        // The saved old values are still needed by the outer checkpoint
+        self.undo_below = checkpoint.undo_below;
+    }
+
+    /// Discard all writes after checkpoint
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        // restore the overwritten values, the latest first
+        for undo in self.undo_entries.drain(checkpoint.undo_entries_len..).rev() {
+            let saved = &self.undo_buffer[undo.undo_offset..undo.undo_offset + undo.len];
+            self.buffer[undo.offset..undo.offset + undo.len].copy_from_slice(saved);
+        }
+        self.undo_buffer.truncate(checkpoint.undo_buffer_len);
+
+        // remove the entries created after checkpoint
+        self.entries.truncate(checkpoint.entries_len);
+        self.buffer.truncate(checkpoint.buffer_len);
+
+        self.undo_below = checkpoint.undo_below;
+    }
+
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.entries.clear();
+        self.undo_entries.clear();
+        self.undo_buffer.clear();
+        self.undo_below = 0;
+    }
+}
+
+struct GuardedEntry<'var> {
+    guard: versioned_lock::Guard<'var>,
+    entry: Entry<'var>,
+}
+
+pub struct Guard<'write_set, 'var> {
+    #[cfg(not(feature = "small_alloc"))]
+    guards: Vec<GuardedEntry<'var>>,
+
+    #[cfg(feature = "small_alloc")]
+    guards: SmallVec<[GuardedEntry<'var>; 16]>,
+    
+    buffer: &'write_set [u8]
+}
+
+impl<'write_set, 'var> Guard<'write_set, 'var> {
+    /// Set all versions of write locks to new version
+    pub fn set_version(&mut self, new_version: Version) {
+        for guarded_entry in &mut self.guards {
+            guarded_entry.guard.set_version(new_version);
+        }
+    }
+
+    pub fn write_data_from_buffer(&mut self) {
+        for guarded_entry in &mut self.guards {
+            // copy the data in buffer to cell
+            // Safety: the TVar was locked by us
+            unsafe {
+                guarded_entry.entry.write_data_from_buffer(self.buffer);
+            }
+
+            // wake up the transactions blocked on this TVar
+            guarded_entry.entry.var.meta.notify_waiters();
+        }
+    }
+
+    /// Check none of the locked TVars was changed since read_version,
+    /// return the changed one otherwise
+    pub fn validate(&self, read_version: Version) -> Result<(), TVarId> {
+        for guarded_entry in &self.guards {
+            // locked by self
+            // make it positive for compare with read_version
+            if -guarded_entry.entry.var.lock.version() > read_version {
+                return Err(guarded_entry.entry.var.id());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Save the old and new values of all locked TVars to their histories
+    pub fn record_history(&self, write_version: Version, depth: usize) {
+        for guarded_entry in &self.guards {
+            let entry = guarded_entry.entry;
+            let history = &entry.var.meta.get_or_init().history;
+
+            // Safety: the TVar was locked by us, nobody is writing it
+            let old_data = unsafe { std::slice::from_raw_parts(entry.var.ptr as *const u8, entry.len) };
+            // the version before locking
+            let old_version = -entry.var.lock.version();
+
+            history.seed(old_version, old_data);
+            history.push(write_version, &self.buffer[entry.offset..entry.offset + entry.len], depth);
+        }
+    }
+
+    pub fn iter_vars(&self) -> impl Iterator<Item = AnyTVar<'var>> + '_ {
+        self.guards
+            .iter()
+            .map(|guard| guard.entry.var)
+    }
+}
@@ -0,0 +1,82 @@
+use std::ops::{Deref, DerefMut};
+
+#[cfg(feature = "small_alloc")]
+use smallvec::SmallVec;
+
+/// The alignment of the buffer, the values can't be aligned more than it
+pub const ALIGN: usize = 64;
+
+#[derive(Clone, Copy)]
+#[repr(C, align(64))]
+struct Block([u8; ALIGN]);
+
+const _: () = assert!(std::mem::align_of::<Block>() == ALIGN);
+
+/// The bytes of the written values, every value is aligned in it
+pub struct Buffer {
+    #[cfg(not(feature = "small_alloc"))]
+    blocks: Vec<Block>,
+
+    // 512B
+    #[cfg(feature = "small_alloc")]
+    blocks: SmallVec<[Block; 8]>,
+
+    // the bytes in use
+    len: usize,
+}
+
+impl Buffer {
+    pub fn new() -> Self {
+        Buffer {
+            // 512B
+            #[cfg(not(feature = "small_alloc"))]
+            blocks: Vec::with_capacity(8),
+
+            #[cfg(feature = "small_alloc")]
+            blocks: SmallVec::new(),
+
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Add `len` bytes aligned to `align`, return their offset
+    pub fn push(&mut self, len: usize, align: usize) -> usize {
+        debug_assert!(align <= ALIGN);
+
+        let offset = self.len.next_multiple_of(align);
+        self.len = offset + len;
+        self.blocks
+            .resize(self.len.div_ceil(ALIGN), Block([0; ALIGN]));
+
+        offset
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        self.len = self.len.min(len);
+        self.blocks.truncate(self.len.div_ceil(ALIGN));
+    }
+
+    pub fn clear(&mut self) {
+        self.truncate(0);
+    }
+}
+
+impl Deref for Buffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        // Safety: the blocks hold at least len bytes
+        unsafe { std::slice::from_raw_parts(self.blocks.as_ptr() as *const u8, self.len) }
+    }
+}
+
+impl DerefMut for Buffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        // Safety: the blocks hold at least len bytes
+        unsafe { std::slice::from_raw_parts_mut(self.blocks.as_mut_ptr() as *mut u8, self.len) }
+    }
+}
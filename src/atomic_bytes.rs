@@ -0,0 +1,131 @@
+//! Copy the `TVar` values in and out with word-sized atomics
+//!
+//! The readers don't take the locks, they may copy a value while the lock holder
+//! is writing it and throw the torn copy away after checking the version (seqlock-style).
+//! Plain copies would be a data race, so every access to the shared bytes that can
+//! race with a writer goes through here.
+//!
+//! The shared side must be aligned to [`ALIGN`], the private side may be unaligned.
+//! The padding bytes of a value are uninitialized, reading them as integers is undefined,
+//! so they are [frozen](freeze) to arbitrary bytes before reaching the shared side.
+//! The shared side keeps them as `MaybeUninit<T>`, which a move copies byte for byte.
+//! Miri can't run the inline asm freezing them, so the types with padding can't be checked there.
+
+use std::mem::{size_of, MaybeUninit};
+use std::sync::atomic::{fence, AtomicU8, AtomicUsize, Ordering};
+
+const WORD: usize = size_of::<usize>();
+
+/// The alignment of the shared storage, at least the one of a word
+pub const ALIGN: usize = 8;
+
+const _: () = assert!(ALIGN >= WORD);
+
+/// Give the uninitialized bytes (the padding) of the `len` bytes at `ptr` arbitrary values
+///
+/// Safety: `ptr` is valid for `len` bytes and not shared with other threads
+#[cfg(all(
+    not(miri),
+    any(
+        target_arch = "x86",
+        target_arch = "x86_64",
+        target_arch = "arm",
+        target_arch = "aarch64",
+        target_arch = "riscv32",
+        target_arch = "riscv64",
+        target_arch = "loongarch64"
+    )
+))]
+pub unsafe fn freeze(ptr: *mut u8, len: usize) {
+    // The compiler must assume the asm block wrote any bytes there, so they are
+    // initialized after it. It actually keeps them as they are
+    std::arch::asm!("/* {0} {1} */", in(reg) ptr, in(reg) len, options(nostack, preserves_flags));
+}
+
+/// Without inline asm the padding can't be frozen, it's copied as it is
+#[cfg(not(all(
+    not(miri),
+    any(
+        target_arch = "x86",
+        target_arch = "x86_64",
+        target_arch = "arm",
+        target_arch = "aarch64",
+        target_arch = "riscv32",
+        target_arch = "riscv64",
+        target_arch = "loongarch64"
+    )
+)))]
+pub unsafe fn freeze(_: *mut u8, _: usize) {}
+
+/// Copy `len` bytes from the shared `src` to the private `dst`
+///
+/// The copy may be torn, the caller checks the version after it
+/// Safety: `src` is valid for `len` bytes and aligned to [`ALIGN`], `dst` is valid for `len` bytes
+pub unsafe fn load(src: *const u8, dst: *mut u8, len: usize) {
+    let mut offset = 0;
+
+    while offset + WORD <= len {
+        let word = (*(src.add(offset) as *const AtomicUsize)).load(Ordering::Relaxed);
+        (dst.add(offset) as *mut usize).write_unaligned(word);
+        offset += WORD;
+    }
+
+    while offset < len {
+        let byte = (*(src.add(offset) as *const AtomicU8)).load(Ordering::Relaxed);
+        dst.add(offset).write(byte);
+        offset += 1;
+    }
+
+    // the version loaded after the copy must not be reordered before it
+    fence(Ordering::Acquire);
+}
+
+/// Copy `len` bytes from the private `src` to the shared `dst`, the padding is frozen
+///
+/// Safety: `dst` is valid for `len` bytes and aligned to [`ALIGN`], `src` is valid for `len` bytes,
+/// and the caller holds the lock of `dst`
+pub unsafe fn store(src: *const u8, dst: *mut u8, len: usize) {
+    // the lock taken before must be visible before any of the bytes
+    fence(Ordering::Release);
+
+    let mut offset = 0;
+
+    while offset + WORD <= len {
+        let mut word = (src.add(offset) as *const MaybeUninit<usize>).read_unaligned();
+        freeze(word.as_mut_ptr() as *mut u8, WORD);
+        (*(dst.add(offset) as *const AtomicUsize)).store(word.assume_init(), Ordering::Relaxed);
+        offset += WORD;
+    }
+
+    while offset < len {
+        let mut byte = (src.add(offset) as *const MaybeUninit<u8>).read();
+        freeze(byte.as_mut_ptr(), 1);
+        (*(dst.add(offset) as *const AtomicU8)).store(byte.assume_init(), Ordering::Relaxed);
+        offset += 1;
+    }
+}
+
+/// Check the shared `current` bytes are the same as the private `expected` ones
+///
+/// Safety: `current` is aligned to [`ALIGN`] and valid for `expected.len()` bytes
+pub unsafe fn equals(current: *const u8, expected: &[u8]) -> bool {
+    let len = expected.len();
+    let src = expected.as_ptr();
+    let mut offset = 0;
+    let mut equal = true;
+
+    while equal && offset + WORD <= len {
+        let word = (*(current.add(offset) as *const AtomicUsize)).load(Ordering::Relaxed);
+        equal = word == (src.add(offset) as *const usize).read_unaligned();
+        offset += WORD;
+    }
+
+    while equal && offset < len {
+        let byte = (*(current.add(offset) as *const AtomicU8)).load(Ordering::Relaxed);
+        equal = byte == src.add(offset).read();
+        offset += 1;
+    }
+
+    fence(Ordering::Acquire);
+    equal
+}
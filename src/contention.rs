@@ -0,0 +1,157 @@
+mod aggressive;
+mod backoff;
+mod karma;
+mod yielding;
+
+pub use aggressive::Aggressive;
+pub use backoff::Backoff;
+pub use karma::{Karma, Polka};
+pub use yielding::Yield;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::TVarId;
+
+/// Decides what a transaction does on conflict
+///
+/// It's asked when a `TVar` lock is held by another transaction
+/// and after an attempt was aborted because of a conflict.
+/// Set it by [`Stm::contention_manager`](crate::Stm::contention_manager),
+/// [`Aggressive`] is the default one
+pub trait ContentionManager {
+    /// A lock needed by the transaction is held by another one,
+    /// return `true` to try it again or `false` to abort the attempt.
+    ///
+    /// `holder` is the priority ([`Progress::work`]) of the transaction holding the lock,
+    /// `spins` is how many times it was tried already
+    fn on_busy_lock(&self, progress: &Progress, holder: usize, spins: usize) -> bool;
+
+    /// The attempt was aborted because of a conflict,
+    /// the transaction will be run again after it returns
+    fn on_abort(&self, progress: &Progress);
+}
+
+/// What a running transaction has done, in all of its attempts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Progress {
+    aborts: usize,
+    work: usize,
+}
+
+impl Progress {
+    /// How many attempts were aborted because of a conflict
+    pub fn aborts(&self) -> usize {
+        self.aborts
+    }
+
+    /// How many `TVar`s were read or written
+    pub fn work(&self) -> usize {
+        self.work
+    }
+
+    pub(crate) fn add_abort(&mut self) {
+        self.aborts += 1;
+    }
+
+    pub(crate) fn add_work(&mut self) {
+        self.work += 1;
+    }
+}
+
+/// The contention manager of the running transaction,
+/// given to [`Algorithm`](crate::Algorithm) to decide how long to wait for a busy lock
+pub struct Contention<'a> {
+    manager: &'a dyn ContentionManager,
+    progress: Progress,
+}
+
+impl<'a> Contention<'a> {
+    pub(crate) fn new(manager: &'a dyn ContentionManager, progress: Progress) -> Self {
+        Contention { manager, progress }
+    }
+
+    /// Wait for the busy locks until they are released,
+    /// the irrevocable transaction must not abort
+    pub(crate) fn irrevocable() -> Self {
+        Contention {
+            manager: &Irrevocable,
+            // Never gives up its locks
+            progress: Progress {
+                aborts: 0,
+                work: usize::MAX,
+            },
+        }
+    }
+
+    /// Whether to try the busy lock of `var` again
+    pub fn on_busy_lock(&self, var: TVarId, spins: usize) -> bool {
+        let holder = holder(var).load(Ordering::Relaxed);
+        self.manager.on_busy_lock(&self.progress, holder, spins)
+    }
+
+    /// The lock of `var` was taken, publish the priority to the transactions waiting for it
+    pub fn on_locked(&self, var: TVarId) {
+        holder(var).store(self.progress.work, Ordering::Relaxed);
+    }
+}
+
+// The priorities of the last lock holders, the TVars share the slots
+// so it's only a hint, like the contention managers are
+// A power of two
+const HOLDER_SLOTS: usize = 1024;
+
+static HOLDERS: [AtomicUsize; HOLDER_SLOTS] = [const { AtomicUsize::new(0) }; HOLDER_SLOTS];
+
+fn holder(var: TVarId) -> &'static AtomicUsize {
+    // Fibonacci hashing, the TVars at the same offsets of different stacks get different slots
+    let hash = (var.addr() as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    &HOLDERS[(hash >> (u64::BITS - HOLDER_SLOTS.trailing_zeros())) as usize]
+}
+
+struct Irrevocable;
+
+impl ContentionManager for Irrevocable {
+    fn on_busy_lock(&self, _: &Progress, _: usize, _: usize) -> bool {
+        // The holder will abort since it cannot commit now
+        std::thread::yield_now();
+        true
+    }
+
+    fn on_abort(&self, _: &Progress) {}
+}
+
+/// Wait for a short time, letting the other threads run meanwhile
+fn pause(duration: Duration) {
+    let start = Instant::now();
+
+    while start.elapsed() < duration {
+        // Let the lock holder run if they share the CPU
+        std::thread::yield_now();
+    }
+}
+
+/// A cheap random number in `0..=bound`
+fn random(bound: u64) -> u64 {
+    use std::cell::Cell;
+
+    thread_local! {
+        static SEED: Cell<u64> = Cell::new({
+            // Different in every thread
+            let local = 0u8;
+            (&local as *const u8 as u64) | 1
+        });
+    }
+
+    // xorshift
+    let x = SEED.with(|seed| {
+        let mut x = seed.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        seed.set(x);
+        x
+    });
+
+    x % bound.saturating_add(1)
+}
@@ -0,0 +1,37 @@
+use super::{ContentionManager, Progress};
+
+/// Spin on a busy lock a few times and run the aborted transaction again at once
+///
+/// It's the cheapest one when the conflicts are rare,
+/// but the hot `TVar`s may suffer from retry storms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Aggressive {
+    spins: usize,
+}
+
+impl Aggressive {
+    pub fn new() -> Self {
+        Aggressive { spins: 10 }
+    }
+
+    /// Try a busy lock `spins` times before aborting
+    pub fn spins(mut self, spins: usize) -> Self {
+        self.spins = spins;
+        self
+    }
+}
+
+impl Default for Aggressive {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContentionManager for Aggressive {
+    fn on_busy_lock(&self, _: &Progress, _: usize, spins: usize) -> bool {
+        std::hint::spin_loop();
+        spins < self.spins
+    }
+
+    fn on_abort(&self, _: &Progress) {}
+}
@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+use super::{pause, random, ContentionManager, Progress};
+
+/// Wait longer after every abort before running the transaction again
+///
+/// The waiting time doubles from `min` up to `max`,
+/// it's a random time below that if [`randomized`](Backoff::randomized),
+/// so the conflicting transactions are less likely to meet again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    min: Duration,
+    max: Duration,
+    randomized: bool,
+    spins: usize,
+}
+
+impl Backoff {
+    pub fn new() -> Self {
+        Backoff {
+            min: Duration::from_micros(1),
+            max: Duration::from_millis(1),
+            randomized: false,
+            spins: 10,
+        }
+    }
+
+    /// The waiting time after the first abort
+    pub fn min(mut self, min: Duration) -> Self {
+        self.min = min;
+        self
+    }
+
+    /// The longest waiting time
+    pub fn max(mut self, max: Duration) -> Self {
+        self.max = max;
+        self
+    }
+
+    /// Wait a random time up to the exponential one
+    pub fn randomized(mut self) -> Self {
+        self.randomized = true;
+        self
+    }
+
+    /// Try a busy lock `spins` times before aborting
+    pub fn spins(mut self, spins: usize) -> Self {
+        self.spins = spins;
+        self
+    }
+
+    pub(crate) fn delay(&self, aborts: usize) -> Duration {
+        let factor = 1u32
+            .checked_shl(aborts.saturating_sub(1) as u32)
+            .unwrap_or(u32::MAX);
+        let delay = self.min.saturating_mul(factor).min(self.max);
+
+        if self.randomized {
+            Duration::from_nanos(random(delay.as_nanos() as u64))
+        } else {
+            delay
+        }
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContentionManager for Backoff {
+    fn on_busy_lock(&self, _: &Progress, _: usize, spins: usize) -> bool {
+        std::hint::spin_loop();
+        spins < self.spins
+    }
+
+    fn on_abort(&self, progress: &Progress) {
+        pause(self.delay(progress.aborts()));
+    }
+}
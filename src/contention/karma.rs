@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use super::{pause, Backoff, ContentionManager, Progress};
+
+/// Karma: the priority of a transaction is the work it has done in all attempts
+///
+/// A busy lock held by a transaction with a higher priority aborts the attempt at once,
+/// otherwise it's waited for as many times as the own priority before aborting.
+/// So the ones having done a lot of work (and lost a lot on abort) win the conflicts,
+/// and the losers gain priority with every attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Karma {
+    // the waits even without any work done
+    min_spins: usize,
+}
+
+impl Karma {
+    pub fn new() -> Self {
+        Karma { min_spins: 10 }
+    }
+
+    /// Wait a busy lock held by a lower priority at least `spins` times
+    pub fn min_spins(mut self, spins: usize) -> Self {
+        self.min_spins = spins;
+        self
+    }
+
+    // How many times to wait for the holder, 0 if it wins
+    fn patience(&self, progress: &Progress, holder: usize) -> usize {
+        if progress.work() < holder {
+            return 0;
+        }
+
+        self.min_spins.max(progress.work())
+    }
+}
+
+impl Default for Karma {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContentionManager for Karma {
+    fn on_busy_lock(&self, progress: &Progress, holder: usize, spins: usize) -> bool {
+        if spins >= self.patience(progress, holder) {
+            return false;
+        }
+
+        std::thread::yield_now();
+        true
+    }
+
+    fn on_abort(&self, _: &Progress) {}
+}
+
+/// Polka: [`Karma`] with an exponential backoff between the waits of a busy lock,
+/// and after the aborts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Polka {
+    karma: Karma,
+    backoff: Backoff,
+}
+
+impl Polka {
+    pub fn new() -> Self {
+        Polka {
+            karma: Karma::new(),
+            backoff: Backoff::new()
+                .min(Duration::from_nanos(100))
+                .max(Duration::from_micros(100)),
+        }
+    }
+
+    /// Wait a busy lock held by a lower priority at least `spins` times
+    pub fn min_spins(mut self, spins: usize) -> Self {
+        self.karma = self.karma.min_spins(spins);
+        self
+    }
+
+    /// The backoff used between the waits and after the aborts
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+}
+
+impl Default for Polka {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContentionManager for Polka {
+    fn on_busy_lock(&self, progress: &Progress, holder: usize, spins: usize) -> bool {
+        if spins >= self.karma.patience(progress, holder) {
+            return false;
+        }
+
+        pause(self.backoff.delay(spins + 1));
+        true
+    }
+
+    fn on_abort(&self, progress: &Progress) {
+        pause(self.backoff.delay(progress.aborts()));
+    }
+}
@@ -0,0 +1,39 @@
+use super::{ContentionManager, Progress};
+
+/// Give up the time slice on a busy lock and after an abort
+///
+/// Suits the machines with more threads than cores,
+/// the lock holder can finish instead of being preempted by the spinning ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Yield {
+    spins: usize,
+}
+
+impl Yield {
+    pub fn new() -> Self {
+        Yield { spins: 10 }
+    }
+
+    /// Try a busy lock `spins` times before aborting
+    pub fn spins(mut self, spins: usize) -> Self {
+        self.spins = spins;
+        self
+    }
+}
+
+impl Default for Yield {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContentionManager for Yield {
+    fn on_busy_lock(&self, _: &Progress, _: usize, spins: usize) -> bool {
+        std::thread::yield_now();
+        spins < self.spins
+    }
+
+    fn on_abort(&self, _: &Progress) {
+        std::thread::yield_now();
+    }
+}
@@ -1,65 +1,220 @@
-use crate::{version::Version, version_clock::VersionClock, StmError, TVar};
+use std::time::Instant;
 
-mod readonly;
-mod write;
+use crate::{
+    algorithm::Algorithm, epoch::Reclaim, CommitInfo, Contention, ContentionManager, Progress, SafeRead, StmError,
+    TVar, Tl2,
+};
 
-// Hide the details for user
-enum ContextInternal<'var> {
-    ReadOnly(readonly::Context<'var>),
-    Write(write::Context<'var>),
+/// The context of a running transaction
+///
+/// All reads and writes of `TVar`s in a transaction go through it,
+/// the details are decided by the [`Algorithm`] of [`Stm`](crate::Stm)
+pub struct Context<'var, A: Algorithm = Tl2> {
+    algorithm: &'var A,
+    internal: A::Context<'var>,
+    contention_manager: &'var dyn ContentionManager,
+    progress: Progress,
+    irrevocable: bool,
+    // the pointers replaced in TBox/TArc
+    reclaim: Reclaim,
 }
 
-pub struct Context<'var> {
-    internal: ContextInternal<'var>,
+/// The state of the context that can be rolled back to
+pub(crate) struct Checkpoint<A: Algorithm> {
+    internal: A::Checkpoint,
+    reclaim: usize,
 }
 
 // Public methods
-impl<'var> Context<'var> {
-    pub fn read<T: Copy>(&mut self, var: &'var TVar<T>) -> Result<T, StmError> {
-        match &mut self.internal {
-            ContextInternal::ReadOnly(context) => context.read(var),
-            ContextInternal::Write(context) => context.read(var),
-        }
+impl<'var, A: Algorithm> Context<'var, A> {
+    pub fn read<T: SafeRead, E>(&mut self, var: &'var TVar<T>) -> Result<T, StmError<E>> {
+        self.progress.add_work();
+
+        self.algorithm
+            .read(&mut self.internal, var)
+            .map_err(StmError::into_abort)
     }
 
-    pub fn write<T: Copy>(&mut self, var: &'var TVar<T>, value: T) -> Result<(), StmError> {
-        match &mut self.internal {
-            ContextInternal::ReadOnly(context) => context.write(var, value),
-            ContextInternal::Write(context) => context.write(var, value),
-        }
+    /// Run `f` on the value of `var` and return its result, instead of returning the value
+    ///
+    /// The value written in this transaction is used in place,
+    /// so is the committed one when nobody else can write it meanwhile:
+    /// a `TVar` locked by this eager transaction, or any `TVar` read irrevocably
+    /// with the lazy [`Tl2`](crate::Tl2) or [`NOrec`](crate::NOrec).
+    /// Otherwise the committed value, or the old one from the history, is copied once
+    /// and validated before `f` runs, so `f` never sees an inconsistent value
+    /// ```
+    /// # use xstm::{Stm, TVar};
+    /// let stm = Stm::new();
+    /// let samples = TVar::new([3u32, 9, 4, 1]);
+    ///
+    /// let max = stm.atomically_fn(|context| context.inspect(&samples, |s| s.iter().copied().max()));
+    /// assert_eq!(max, Some(9));
+    /// ```
+    pub fn inspect<T: SafeRead, R, E>(
+        &mut self,
+        var: &'var TVar<T>,
+        f: impl FnOnce(&T) -> R,
+    ) -> Result<R, StmError<E>> {
+        self.progress.add_work();
+
+        self.algorithm
+            .inspect(&mut self.internal, var, f)
+            .map_err(StmError::into_abort)
+    }
+
+    pub fn write<T: SafeRead, E>(&mut self, var: &'var TVar<T>, value: T) -> Result<(), StmError<E>> {
+        self.progress.add_work();
+
+        let contention = Contention::new(self.contention_manager, self.progress);
+        self.algorithm
+            .write(&mut self.internal, var, value, &contention)
+            .map_err(StmError::into_abort)
+    }
+
+    /// Run `f` on the value of `var` to change it, like a read followed by a write
+    ///
+    /// The value is copied into the write buffer once,
+    /// the later updates in the same transaction change it there
+    /// ```
+    /// # use xstm::{Stm, TVar};
+    /// let stm = Stm::new();
+    /// let slots = TVar::new([0u32; 64]);
+    ///
+    /// stm.atomically_fn(|context| context.update(&slots, |s| s[7] = 1));
+    /// assert_eq!(stm.atomically(slots.read())[7], 1);
+    /// ```
+    pub fn update<T: SafeRead, R, E>(
+        &mut self,
+        var: &'var TVar<T>,
+        f: impl FnOnce(&mut T) -> R,
+    ) -> Result<R, StmError<E>> {
+        self.progress.add_work();
+
+        let contention = Contention::new(self.contention_manager, self.progress);
+        self.algorithm
+            .update(&mut self.internal, var, f, &contention)
+            .map_err(StmError::into_abort)
+    }
+
+    /// Abort the transaction and block the current thread
+    /// until one of the `TVar`s read so far is changed by another transaction,
+    /// then run the transaction again
+    ///
+    /// If nothing was read, nothing can wake it up, it blocks until the deadline
+    /// of [`Limit::Deadline`](crate::Limit::Deadline), or forever without one
+    pub fn retry<T, E>(&mut self) -> Result<T, StmError<E>> {
+        Err(StmError::Wait)
+    }
+
+    /// Stop the transaction for good, all writes are discarded
+    /// and [`Stm::try_atomically`](crate::Stm::try_atomically) returns `error`
+    pub fn abort<T, E>(&mut self, error: E) -> Result<T, StmError<E>> {
+        Err(StmError::Abort(error))
     }
 }
 
 // Internal methods
-impl<'var> Context<'var> {
-    pub(crate) fn new(read_version: Version) -> Self {
+impl<'var, A: Algorithm> Context<'var, A> {
+    pub(crate) fn new(algorithm: &'var A, contention_manager: &'var dyn ContentionManager) -> Self {
         Context {
-            internal: ContextInternal::ReadOnly(readonly::Context::new(read_version)),
+            algorithm,
+            internal: algorithm.new_context(),
+            contention_manager,
+            progress: Progress::default(),
+            irrevocable: false,
+            reclaim: Reclaim::default(),
         }
     }
 
-    pub(crate) fn reset(&mut self, read_version: Version) {
-        match &mut self.internal {
-            ContextInternal::ReadOnly(context) => {
-                if context.tried_writing() {
-                    // Tried to write in read-only context
-                    // Convert it to write context
-                    self.internal = ContextInternal::Write(write::Context::new(read_version))
-                } else {
-                    // just reset the read_only context
-                    context.reset(read_version);
-                }
-            }
-            ContextInternal::Write(context) => {
-                context.reset(read_version);
-            }
+    pub(crate) fn reset(&mut self) {
+        self.algorithm.reset(&mut self.internal)
+    }
+
+    /// Whether the next attempt runs in another context
+    pub(crate) fn switches_context(&self) -> bool {
+        self.algorithm.switches_context(&self.internal)
+    }
+
+    /// How many attempts were aborted because of a conflict
+    pub(crate) fn aborts(&self) -> usize {
+        self.progress.aborts()
+    }
+
+    /// Stop other transactions committing until the attempt ends
+    pub(crate) fn begin_irrevocable(&mut self) {
+        self.algorithm.begin_irrevocable(&mut self.internal);
+        self.irrevocable = true;
+    }
+
+    pub(crate) fn end_irrevocable(&mut self) {
+        if self.irrevocable {
+            self.irrevocable = false;
+            self.algorithm.end_irrevocable(&mut self.internal);
         }
     }
 
-    pub(crate) fn try_commit(&mut self, clock: &VersionClock) -> Result<(), StmError>{
-        match &mut self.internal {
-            ContextInternal::ReadOnly(context) => context.try_commit(),
-            ContextInternal::Write(context) => context.try_commit(clock),
+    pub(crate) fn try_commit(&mut self) -> Result<CommitInfo, StmError> {
+        let contention = Contention::new(self.contention_manager, self.progress);
+        self.algorithm.commit(&mut self.internal, &contention)
+    }
+
+    /// Release what the failed attempt holds before waiting for anything
+    pub(crate) fn abort_attempt(&mut self) {
+        self.algorithm.abort(&mut self.internal)
+    }
+
+    /// The attempt was aborted, let the contention manager handle the conflict
+    pub(crate) fn on_abort(&mut self) {
+        if !self.algorithm.conflicted(&self.internal) {
+            return;
         }
+
+        self.progress.add_abort();
+        self.contention_manager.on_abort(&self.progress)
+    }
+
+    pub(crate) fn checkpoint(&mut self) -> Checkpoint<A> {
+        Checkpoint {
+            internal: self.algorithm.checkpoint(&mut self.internal),
+            reclaim: self.reclaim.checkpoint(),
+        }
+    }
+
+    /// Keep the writes after checkpoint
+    pub(crate) fn release(&mut self, checkpoint: Checkpoint<A>) {
+        self.algorithm.release(&mut self.internal, checkpoint.internal)
+    }
+
+    /// Discard the writes after checkpoint
+    pub(crate) fn rollback(&mut self, checkpoint: Checkpoint<A>) {
+        self.algorithm.rollback(&mut self.internal, checkpoint.internal);
+        // Safety: rolled back just now
+        unsafe { self.reclaim.rollback(checkpoint.reclaim) };
+    }
+
+    pub(crate) fn reclaim(&mut self) -> &mut Reclaim {
+        &mut self.reclaim
+    }
+
+    /// The attempt committed or aborted, settle the pointers replaced in it
+    /// The aborted attempt must be [released](Self::abort_attempt) before
+    pub(crate) fn end_attempt(&mut self, committed: bool) {
+        // Safety: the writes of an aborted attempt were released with the locks
+        unsafe { self.reclaim.end(committed) }
+    }
+
+    /// Block until one of the `TVar`s in read set was changed or the deadline passed
+    pub(crate) fn wait(&mut self, deadline: Option<Instant>) {
+        self.algorithm.wait(&mut self.internal, deadline)
+    }
+}
+
+impl<'var, A: Algorithm> Drop for Context<'var, A> {
+    fn drop(&mut self) {
+        // Aborted or panicked in an irrevocable attempt
+        self.end_irrevocable();
+        self.abort_attempt();
+        self.end_attempt(false);
     }
 }
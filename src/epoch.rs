@@ -0,0 +1,279 @@
+//! Epoch-based reclamation of the values replaced in [`TBox`](crate::TBox)/[`TArc`](crate::TArc)
+//!
+//! A transaction is pinned to the global epoch before reading a pointer,
+//! the replaced pointers are retired with the epoch at that time.
+//! The global epoch only advances when every pinned thread has seen the current one,
+//! so a retired value is freed two epochs later, when no reader can still hold it.
+//! Every thread keeps its retired values and frees them in batches,
+//! the values left by the exited threads are freed by the others.
+
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::sync::atomic::{fence, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+// 0 when unpinned, the pinned epoch shifted left by one and the lowest bit set otherwise
+#[derive(Debug, Default)]
+struct Local {
+    state: AtomicUsize,
+}
+
+// Collect the garbage of a thread after this many retirements
+const COLLECT_EVERY: usize = 64;
+
+struct Handle {
+    local: Arc<Local>,
+    pins: Cell<usize>,
+    // the values retired by this thread, in the order of their epochs
+    bag: RefCell<VecDeque<(usize, Garbage)>>,
+    retires: Cell<usize>,
+}
+
+impl Handle {
+    fn new() -> Self {
+        let local = Arc::new(Local::default());
+        lock(&GLOBAL.locals).push(local.clone());
+
+        Handle {
+            local,
+            pins: Cell::new(0),
+            bag: RefCell::new(VecDeque::new()),
+            retires: Cell::new(0),
+        }
+    }
+
+    /// Free the values in the bag retired long enough before
+    fn collect(&self) {
+        let epoch = try_advance();
+
+        let ripe = {
+            let mut bag = self.bag.borrow_mut();
+            let count = bag.iter().take_while(|(retired, _)| retired + 2 <= epoch).count();
+            bag.drain(..count).collect::<Vec<_>>()
+        };
+
+        // The drops may retire more values
+        for (_, garbage) in ripe {
+            unsafe { garbage.free() };
+        }
+
+        collect_orphans(epoch);
+    }
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        lock(&GLOBAL.locals).retain(|local| !Arc::ptr_eq(local, &self.local));
+
+        // Left to the other threads
+        lock(&GLOBAL.orphans).extend(self.bag.get_mut().drain(..));
+
+        // Nothing may be retired after the last thread exits, so free what the pins allow now,
+        // the values retired in the current epoch are ripe two epochs later
+        try_advance();
+        collect_orphans(try_advance());
+    }
+}
+
+struct Global {
+    epoch: AtomicUsize,
+    locals: Mutex<Vec<Arc<Local>>>,
+    // the values left by the exited threads
+    orphans: Mutex<Vec<(usize, Garbage)>>,
+}
+
+static GLOBAL: Global = Global {
+    epoch: AtomicUsize::new(0),
+    locals: Mutex::new(Vec::new()),
+    orphans: Mutex::new(Vec::new()),
+};
+
+thread_local! {
+    static HANDLE: Handle = Handle::new();
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// A value to be dropped when no reader can reach it
+pub struct Garbage {
+    ptr: *const (),
+    drop: unsafe fn(*const ()),
+}
+
+// Safety: only created for the values that can be dropped on any thread
+unsafe impl Send for Garbage {}
+
+impl Garbage {
+    /// Safety: `drop(ptr)` frees the value once, it can be called on any thread
+    pub unsafe fn new(ptr: *const (), drop: unsafe fn(*const ())) -> Self {
+        Garbage { ptr, drop }
+    }
+
+    /// Safety: no reader can reach the value any more
+    pub unsafe fn free(self) {
+        (self.drop)(self.ptr)
+    }
+}
+
+/// Keeps the current thread pinned while alive
+pub struct Guard {
+    // pinned to the current thread
+    _local: PhantomData<*const ()>,
+}
+
+pub fn pin() -> Guard {
+    HANDLE.with(|handle| {
+        let pins = handle.pins.get();
+        handle.pins.set(pins + 1);
+
+        if pins == 0 {
+            let epoch = GLOBAL.epoch.load(Ordering::Relaxed);
+            handle.local.state.store(epoch << 1 | 1, Ordering::Relaxed);
+            // the pointers must be read after the epoch is published
+            fence(Ordering::SeqCst);
+        }
+    });
+
+    Guard {
+        _local: PhantomData,
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        // The handle may be gone while the thread exits
+        let _ = HANDLE.try_with(|handle| {
+            let pins = handle.pins.get() - 1;
+            handle.pins.set(pins);
+
+            if pins == 0 {
+                handle.local.state.store(0, Ordering::Release);
+            }
+        });
+    }
+}
+
+/// Drop the value after all the threads pinned now are unpinned,
+/// and free the values retired long enough before once in a while
+pub fn retire(garbage: Garbage) {
+    let entry = (GLOBAL.epoch.load(Ordering::SeqCst), garbage);
+
+    let mut entry = Some(entry);
+    let _ = HANDLE.try_with(|handle| {
+        handle.bag.borrow_mut().extend(entry.take());
+
+        let retires = handle.retires.get() + 1;
+        handle.retires.set(retires);
+        if retires % COLLECT_EVERY == 0 {
+            handle.collect();
+        }
+    });
+
+    // The thread is exiting
+    if let Some(entry) = entry {
+        lock(&GLOBAL.orphans).push(entry);
+    }
+}
+
+// Free the values left by the exited threads, retired two epochs before `epoch`
+fn collect_orphans(epoch: usize) {
+    let ripe = {
+        let mut list = lock(&GLOBAL.orphans);
+        if list.is_empty() {
+            return;
+        }
+
+        let (ripe, kept) = std::mem::take(&mut *list)
+            .into_iter()
+            .partition::<Vec<_>, _>(|(retired, _)| retired + 2 <= epoch);
+        *list = kept;
+        ripe
+    };
+
+    // The drops may retire more values
+    for (_, garbage) in ripe {
+        unsafe { garbage.free() };
+    }
+}
+
+// Advance the global epoch if every pinned thread has seen it, return the latest one
+fn try_advance() -> usize {
+    let epoch = GLOBAL.epoch.load(Ordering::SeqCst);
+    fence(Ordering::SeqCst);
+
+    let behind = lock(&GLOBAL.locals).iter().any(|local| {
+        let state = local.state.load(Ordering::Relaxed);
+        state & 1 == 1 && state >> 1 != epoch
+    });
+
+    if behind {
+        return epoch;
+    }
+
+    match GLOBAL
+        .epoch
+        .compare_exchange(epoch, epoch + 1, Ordering::SeqCst, Ordering::SeqCst)
+    {
+        Ok(_) => epoch + 1,
+        Err(current) => current,
+    }
+}
+
+// A replaced pointer, and the one written in its place
+struct Change {
+    old: Garbage,
+    new: Garbage,
+}
+
+/// The pointers replaced in a transaction attempt, and the pin of the attempt
+#[derive(Default)]
+pub struct Reclaim {
+    guard: Option<Guard>,
+    changes: Vec<Change>,
+}
+
+impl Reclaim {
+    /// Pin the attempt before reading any pointer, until it ends
+    pub fn pin(&mut self) {
+        if self.guard.is_none() {
+            self.guard = Some(pin());
+        }
+    }
+
+    /// `old` is dropped if the attempt commits, `new` otherwise
+    pub fn replace(&mut self, old: Garbage, new: Garbage) {
+        self.changes.push(Change { old, new });
+    }
+
+    pub fn checkpoint(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// The writes after checkpoint were discarded, so are the new values
+    /// Safety: the writes were rolled back already
+    pub unsafe fn rollback(&mut self, checkpoint: usize) {
+        for change in self.changes.drain(checkpoint..) {
+            // Never published, or only in a TVar locked by us, which nobody else reads
+            change.new.free();
+        }
+    }
+
+    /// Unpin, then retire the replaced values if committed, free the written ones otherwise
+    /// Safety: the writes of an aborted attempt were rolled back already
+    pub unsafe fn end(&mut self, committed: bool) {
+        // Our own pin would keep the epoch from advancing
+        self.guard = None;
+
+        for change in self.changes.drain(..) {
+            if committed {
+                retire(change.old);
+            } else {
+                // Never published, like the rolled back ones
+                change.new.free();
+            }
+        }
+    }
+}
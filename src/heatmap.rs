@@ -0,0 +1,111 @@
+use std::{collections::HashMap, fmt::Display, sync::Mutex};
+
+use crate::{AbortReason, TVarId};
+
+/// The conflicts counted by `TVar` and transaction kind
+pub(crate) struct Heatmap {
+    conflicts: Mutex<HashMap<TVarId, HashMap<&'static str, u64>>>,
+}
+
+impl Heatmap {
+    pub fn new() -> Self {
+        Heatmap {
+            conflicts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count the abort if the `TVar` causing it is known
+    pub fn abort(&self, reason: AbortReason, kind: &'static str) {
+        let var = match reason {
+            AbortReason::ReadValidation { var }
+            | AbortReason::LockContention { var }
+            | AbortReason::CommitValidation { var } => var,
+            _ => None,
+        };
+
+        if let Some(var) = var {
+            let mut conflicts = self.conflicts.lock().unwrap_or_else(|err| err.into_inner());
+            *conflicts.entry(var).or_default().entry(kind).or_default() += 1;
+        }
+    }
+
+    pub fn report(&self) -> ConflictReport {
+        let conflicts = self.conflicts.lock().unwrap_or_else(|err| err.into_inner());
+
+        let mut vars: Vec<_> = conflicts
+            .iter()
+            .map(|(var, kinds)| {
+                let mut transactions: Vec<_> =
+                    kinds.iter().map(|(kind, aborts)| (*kind, *aborts)).collect();
+                transactions.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+                VarConflicts {
+                    var: *var,
+                    aborts: transactions.iter().map(|(_, aborts)| aborts).sum(),
+                    transactions,
+                }
+            })
+            .collect();
+        vars.sort_by(|a, b| b.aborts.cmp(&a.aborts).then(a.var.cmp(&b.var)));
+
+        ConflictReport { vars }
+    }
+}
+
+/// The `TVar`s causing aborts, the most contended first,
+/// got by [`Stm::conflict_report`](crate::Stm::conflict_report)
+///
+/// It's printed as a table by `Display`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictReport {
+    vars: Vec<VarConflicts>,
+}
+
+impl ConflictReport {
+    pub fn vars(&self) -> &[VarConflicts] {
+        &self.vars
+    }
+
+    /// The `n` most contended `TVar`s
+    pub fn top(&self, n: usize) -> &[VarConflicts] {
+        &self.vars[..n.min(self.vars.len())]
+    }
+}
+
+impl Display for ConflictReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for var in &self.vars {
+            writeln!(f, "{}: {} aborts", var.var, var.aborts)?;
+
+            for (kind, aborts) in &var.transactions {
+                writeln!(f, "    {}: {}", kind, aborts)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The aborts caused by a `TVar`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VarConflicts {
+    var: TVarId,
+    aborts: u64,
+    transactions: Vec<(&'static str, u64)>,
+}
+
+impl VarConflicts {
+    pub fn var(&self) -> TVarId {
+        self.var
+    }
+
+    pub fn aborts(&self) -> u64 {
+        self.aborts
+    }
+
+    /// The kinds (type names) of the aborted transactions and their aborts,
+    /// the most aborted first
+    pub fn transactions(&self) -> &[(&'static str, u64)] {
+        &self.transactions
+    }
+}
@@ -0,0 +1,110 @@
+use std::{collections::VecDeque, sync::Mutex};
+
+use crate::version::Version;
+
+/// The latest committed values of a TVar
+///
+/// Only written when the multi-version mode is used,
+/// the versions are in ascending order
+#[derive(Debug)]
+pub struct History {
+    entries: Mutex<VecDeque<Entry>>,
+    // never recorded if false
+    enabled: bool,
+}
+
+#[derive(Debug)]
+struct Entry {
+    version: Version,
+    data: Box<[u8]>,
+}
+
+impl History {
+    pub fn new() -> History {
+        History {
+            entries: Mutex::new(VecDeque::new()),
+            enabled: true,
+        }
+    }
+
+    /// A history never recorded, the reads before the latest version abort
+    pub fn disabled() -> History {
+        History {
+            enabled: false,
+            ..History::new()
+        }
+    }
+
+    /// Save the value before the first write
+    /// The caller must hold the lock of TVar
+    pub fn seed(&self, version: Version, data: &[u8]) {
+        if !self.enabled {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+
+        if entries.is_empty() {
+            entries.push_back(Entry {
+                version,
+                data: data.into(),
+            });
+        }
+    }
+
+    /// Save a new committed value, keep `depth` older values at most
+    /// The caller must hold the lock of TVar, so the versions are pushed in order
+    pub fn push(&self, version: Version, data: &[u8], depth: usize) {
+        if !self.enabled {
+            return;
+        }
+
+        let mut entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+
+        let entry = if entries.len() > depth {
+            // Reuse the oldest one
+            let mut entry = entries.pop_front().unwrap_or_else(|| unreachable!());
+            entry.version = version;
+            entry.data.copy_from_slice(data);
+            entry
+        } else {
+            Entry {
+                version,
+                data: data.into(),
+            }
+        };
+
+        entries.push_back(entry);
+    }
+
+    /// Copy the value valid at read_version to data
+    ///
+    /// Return false if it was dropped already,
+    /// or it may still be overwritten by a committing transaction
+    pub fn read(
+        &self,
+        read_version: Version,
+        written_after: impl Fn(Version) -> bool,
+        data: &mut [u8],
+    ) -> bool {
+        let entries = self.entries.lock().unwrap_or_else(|err| err.into_inner());
+
+        // The latest one is not known to be valid,
+        // a transaction may be committing an older version than read_version.
+        // The next one must be written after the snapshot was taken,
+        // otherwise the snapshot would miss a committed transaction
+        let found = entries
+            .iter()
+            .zip(entries.iter().skip(1))
+            .rev()
+            .find(|(entry, next)| entry.version <= read_version && written_after(next.version));
+
+        match found {
+            Some((entry, _)) => {
+                data.copy_from_slice(&entry.data);
+                true
+            }
+            None => false,
+        }
+    }
+}
@@ -1,29 +1,97 @@
 #![doc = include_str!("../ReadMe.md")]
 
+use std::convert::Infallible;
+
 mod version;
 mod version_clock;
+pub use version_clock::ClockStrategy;
 
 mod transaction;
-pub use transaction::Transaction;
+pub use transaction::{OrElse, Transaction, TransactionExt};
+
+mod algorithm;
+pub use algorithm::{Algorithm, NOrec, Tl2};
 
 mod context;
 pub use context::Context;
 
+mod contention;
+pub use contention::{
+    Aggressive, Backoff, Contention, ContentionManager, Karma, Polka, Progress, Yield,
+};
+
+mod observer;
+pub use observer::{CommitInfo, StmObserver};
+
+mod stats;
+pub use stats::Stats;
+
+mod heatmap;
+pub use heatmap::{ConflictReport, VarConflicts};
+
+mod limit;
+pub use limit::{Failed, Limit};
+
+mod safe_read;
+pub use safe_read::SafeRead;
+
 mod var;
-pub use var::TVar;
+pub use var::{TVar, TVarId};
+
+mod tbox;
+pub use tbox::{TArc, TBox};
 
 mod stm;
 pub use stm::Stm;
 
+mod atomic_bytes;
+mod epoch;
+mod history;
+mod meta;
 mod versioned_lock;
+mod waiter;
 
-#[cfg(feature = "retry_info")]
+/// `E` is the error type of [`Context::abort`]
 #[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
-pub enum StmError {
-    Retry(&'static str),
+pub enum StmError<E = Infallible> {
+    /// The attempt was aborted, the transaction will be run again
+    Retry(AbortReason),
+    /// The transaction called [`Context::retry`],
+    /// it will be blocked until one of the `TVar`s it read was changed
+    Wait,
+    /// The transaction called [`Context::abort`],
+    /// it will be stopped without committing
+    Abort(E),
 }
-#[cfg(not(feature = "retry_info"))]
+
+impl StmError {
+    /// The internal errors never abort, they fit any abort type
+    pub(crate) fn into_abort<E>(self) -> StmError<E> {
+        match self {
+            StmError::Retry(reason) => StmError::Retry(reason),
+            StmError::Wait => StmError::Wait,
+            StmError::Abort(never) => match never {},
+        }
+    }
+}
+
+/// Why an attempt of a transaction was aborted
+///
+/// `var` is the `TVar` causing it, if it's known
 #[derive(Debug, Copy, Clone, PartialEq, PartialOrd, Eq, Ord, Hash)]
-pub enum StmError {
-    Retry,
+pub enum AbortReason {
+    /// A `TVar` read was changed after the snapshot,
+    /// and the snapshot could not be moved forward
+    ReadValidation { var: Option<TVarId> },
+    /// A `TVar` was locked by another transaction
+    LockContention { var: Option<TVarId> },
+    /// A `TVar` read (or written in snapshot isolation) was changed before committing
+    CommitValidation { var: Option<TVarId> },
+    /// The transaction wrote in a read-only context,
+    /// it will be run in a write context
+    WriteInReadOnly,
+    /// The transaction called [`Context::retry`]
+    UserRetry,
+    /// An [irrevocable](Stm::irrevocably) transaction was running
+    Irrevocable,
 }
@@ -0,0 +1,66 @@
+use std::{convert::Infallible, time::Instant};
+
+use crate::AbortReason;
+
+/// When [`Stm::try_atomically_with`](crate::Stm::try_atomically_with) gives up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Limit {
+    /// Run the transaction at most this many times,
+    /// including the attempt switching [`Tl2`](crate::Tl2) to write context.
+    /// A [`retry`](crate::Context::retry) still blocks until a `TVar` it read is changed
+    Attempts(usize),
+    /// Don't run the transaction again after the deadline,
+    /// a [`retry`](crate::Context::retry) is not blocked beyond it either
+    Deadline(Instant),
+}
+
+impl Limit {
+    pub(crate) fn deadline(&self) -> Option<Instant> {
+        match self {
+            Limit::Attempts(_) => None,
+            Limit::Deadline(deadline) => Some(*deadline),
+        }
+    }
+
+    pub(crate) fn reached(&self, attempts: usize) -> bool {
+        match self {
+            Limit::Attempts(limit) => attempts >= *limit,
+            Limit::Deadline(deadline) => Instant::now() >= *deadline,
+        }
+    }
+}
+
+impl From<usize> for Limit {
+    fn from(attempts: usize) -> Self {
+        Limit::Attempts(attempts)
+    }
+}
+
+impl From<Instant> for Limit {
+    fn from(deadline: Instant) -> Self {
+        Limit::Deadline(deadline)
+    }
+}
+
+/// A transaction stopped without committing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Failed<E = Infallible> {
+    /// The transaction called [`Context::abort`](crate::Context::abort)
+    Abort(E),
+    /// The transaction couldn't commit within the [`Limit`]
+    GaveUp {
+        attempts: usize,
+        /// Why the last attempt failed
+        reason: AbortReason,
+    },
+}
+
+impl<E> Failed<E> {
+    /// The transactions without a limit only stop on abort
+    pub(crate) fn into_abort(self) -> E {
+        match self {
+            Failed::Abort(err) => err,
+            Failed::GaveUp { .. } => unreachable!("gave up without a limit"),
+        }
+    }
+}
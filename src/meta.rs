@@ -0,0 +1,95 @@
+use std::ptr;
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+use crate::history::History;
+use crate::waiter::WaitList;
+
+/// The data of a `TVar` used only by the blocked, multi-version or named ones
+#[derive(Debug)]
+pub struct Meta {
+    pub waiters: WaitList,
+    pub history: History,
+    pub name: Option<&'static str>,
+}
+
+impl Meta {
+    pub fn new() -> Meta {
+        Meta {
+            waiters: WaitList::new(),
+            history: History::new(),
+            name: None,
+        }
+    }
+}
+
+/// [`Meta`] allocated on the first use, so a plain `TVar` is only its value,
+/// its lock and one pointer
+#[derive(Debug)]
+pub struct LazyMeta {
+    meta: AtomicPtr<Meta>,
+}
+
+impl LazyMeta {
+    pub fn new() -> LazyMeta {
+        LazyMeta {
+            meta: AtomicPtr::new(ptr::null_mut()),
+        }
+    }
+
+    pub fn with(meta: Meta) -> LazyMeta {
+        LazyMeta {
+            meta: AtomicPtr::new(Box::into_raw(Box::new(meta))),
+        }
+    }
+
+    /// The metadata if it was ever used
+    ///
+    /// `SeqCst` like the waiter count: a committer seeing none here
+    /// is ordered before the registration, which validates again afterwards
+    pub fn get(&self) -> Option<&Meta> {
+        // Safety: never freed before self
+        unsafe { self.meta.load(Ordering::SeqCst).as_ref() }
+    }
+
+    pub fn get_or_init(&self) -> &Meta {
+        if let Some(meta) = self.get() {
+            return meta;
+        }
+
+        let new = Box::into_raw(Box::new(Meta::new()));
+        match self
+            .meta
+            .compare_exchange(ptr::null_mut(), new, Ordering::SeqCst, Ordering::SeqCst)
+        {
+            // Safety: published, never freed before self
+            Ok(_) => unsafe { &*new },
+            Err(current) => {
+                // Another thread was first
+                // Safety: never published
+                drop(unsafe { Box::from_raw(new) });
+                unsafe { &*current }
+            }
+        }
+    }
+
+    pub fn name(&self) -> Option<&'static str> {
+        self.get().and_then(|meta| meta.name)
+    }
+
+    /// Wake up the transactions blocked on the `TVar`
+    pub fn notify_waiters(&self) {
+        if let Some(meta) = self.get() {
+            meta.waiters.notify_all();
+        }
+    }
+}
+
+impl Drop for LazyMeta {
+    fn drop(&mut self) {
+        let meta = *self.meta.get_mut();
+        if !meta.is_null() {
+            // Safety: allocated by us, nobody else can reach it
+            drop(unsafe { Box::from_raw(meta) });
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use crate::AbortReason;
+
+/// Watches the transactions run by [`Stm`](crate::Stm), e.g. for logging or metrics
+///
+/// Set it by [`Stm::observer`](crate::Stm::observer), all methods do nothing by default.
+/// `()` is the default observer, the calls to it are compiled away
+pub trait StmObserver {
+    /// An attempt of a transaction is started, `attempt` counts from 1
+    fn on_attempt_start(&self, _attempt: usize) {}
+
+    /// The attempt was aborted, the transaction will be run again unless it reached the limit
+    fn on_abort(&self, _reason: AbortReason) {}
+
+    /// The transaction was committed
+    fn on_commit(&self, _commit: &CommitInfo) {}
+
+    /// The transaction aborted `aborts` times by conflicts
+    /// will be run [irrevocably](crate::Stm::irrevocable_after)
+    fn on_escalation(&self, _aborts: usize) {}
+}
+
+impl StmObserver for () {}
+
+impl<O: StmObserver + ?Sized> StmObserver for &O {
+    fn on_attempt_start(&self, attempt: usize) {
+        (**self).on_attempt_start(attempt)
+    }
+
+    fn on_abort(&self, reason: AbortReason) {
+        (**self).on_abort(reason)
+    }
+
+    fn on_commit(&self, commit: &CommitInfo) {
+        (**self).on_commit(commit)
+    }
+
+    fn on_escalation(&self, aborts: usize) {
+        (**self).on_escalation(aborts)
+    }
+}
+
+/// What a committed transaction has done
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CommitInfo {
+    reads: usize,
+    writes: usize,
+    write_version: Option<u64>,
+}
+
+impl CommitInfo {
+    pub fn new(reads: usize, writes: usize, write_version: Option<u64>) -> Self {
+        CommitInfo {
+            reads,
+            writes,
+            write_version,
+        }
+    }
+
+    /// The size of the read set,
+    /// or the number of reads if the algorithm didn't log them
+    pub fn reads(&self) -> usize {
+        self.reads
+    }
+
+    /// The size of the write set
+    pub fn writes(&self) -> usize {
+        self.writes
+    }
+
+    /// The version the writes were committed at, `None` for a read-only transaction
+    pub fn write_version(&self) -> Option<u64> {
+        self.write_version
+    }
+}
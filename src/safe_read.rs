@@ -0,0 +1,46 @@
+/// Types that can be held by a [`TVar`](crate::TVar)
+///
+/// The readers copy the bytes of the value without locking, and throw the copy away
+/// if a writer changed the value meanwhile. So the values are moved in and out of
+/// a `TVar` as bytes, like a `Copy` type, and the overwritten ones are never dropped.
+///
+/// It's implemented for all `Copy` types. It can also be implemented for the
+/// plain-old-data types that are `Clone` but not `Copy`,
+/// such as fixed-capacity strings or inline vectors.
+/// ```
+/// # use xstm::{SafeRead, Stm, TVar};
+/// #[derive(Clone)]
+/// struct Name {
+///     len: u8,
+///     bytes: [u8; 15],
+/// }
+///
+/// // Safety: the bytes are all the state, a copy owns nothing
+/// unsafe impl SafeRead for Name {}
+///
+/// let stm = Stm::new();
+/// let name = TVar::new(Name { len: 3, bytes: *b"stm\0\0\0\0\0\0\0\0\0\0\0\0" });
+/// assert_eq!(stm.atomically(name.read()).len, 3);
+/// ```
+///
+/// # Safety
+/// A bitwise copy of a value must be a valid value behaving like a clone of it,
+/// and dropping one copy (or never dropping it) must not affect the others.
+/// So the types owning heap memory, handles or references counts cannot implement it.
+///
+/// The copies are used on the threads reading them, at the same time,
+/// so a `TVar` can only be shared between threads when its type is also `Send + Sync`:
+/// ```compile_fail
+/// # use std::cell::Cell;
+/// # use xstm::{Stm, TVar};
+/// let stm = Stm::new();
+/// let cell = Cell::new(0);
+/// let var = TVar::new(&cell);
+///
+/// std::thread::scope(|scope| {
+///     scope.spawn(|| stm.atomically(var.read()).set(1));
+/// });
+/// ```
+pub unsafe trait SafeRead: Clone {}
+
+unsafe impl<T: Copy> SafeRead for T {}
@@ -0,0 +1,190 @@
+use std::{
+    cell::Cell,
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+};
+
+use crate::{AbortReason, CommitInfo};
+
+const SHARDS: usize = 16;
+// Writing in read-only context is only counted as a promotion
+const REASONS: usize = 5;
+
+/// The counters of a [`Stm`](crate::Stm), updated by all transactions
+///
+/// Every thread counts in one of the shards, so they rarely share a cache line
+pub(crate) struct Counters {
+    shards: [Shard; SHARDS],
+}
+
+#[derive(Default)]
+#[repr(align(128))]
+struct Shard {
+    read_only_commits: AtomicU64,
+    write_commits: AtomicU64,
+    reads: AtomicU64,
+    writes: AtomicU64,
+    promotions: AtomicU64,
+    aborts: [AtomicU64; REASONS],
+}
+
+impl Counters {
+    pub fn new() -> Self {
+        Counters {
+            shards: Default::default(),
+        }
+    }
+
+    fn shard(&self) -> &Shard {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+
+        thread_local! {
+            static INDEX: Cell<usize> = const { Cell::new(usize::MAX) };
+        }
+
+        let index = INDEX.with(|index| {
+            if index.get() == usize::MAX {
+                index.set(NEXT.fetch_add(1, Ordering::Relaxed) % SHARDS);
+            }
+            index.get()
+        });
+
+        &self.shards[index]
+    }
+
+    pub fn commit(&self, commit: &CommitInfo) {
+        let shard = self.shard();
+
+        if commit.write_version().is_some() {
+            shard.write_commits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            shard.read_only_commits.fetch_add(1, Ordering::Relaxed);
+        }
+
+        shard.reads.fetch_add(commit.reads() as u64, Ordering::Relaxed);
+        shard.writes.fetch_add(commit.writes() as u64, Ordering::Relaxed);
+    }
+
+    pub fn abort(&self, reason: AbortReason) {
+        let index = match reason {
+            AbortReason::ReadValidation { .. } => 0,
+            AbortReason::LockContention { .. } => 1,
+            AbortReason::CommitValidation { .. } => 2,
+            AbortReason::UserRetry => 3,
+            AbortReason::Irrevocable => 4,
+            AbortReason::WriteInReadOnly => return,
+        };
+
+        self.shard().aborts[index].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// The next attempt runs in write context
+    pub fn promotion(&self) {
+        self.shard().promotions.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Sum up the shards
+    pub fn snapshot(&self) -> Stats {
+        let mut stats = Stats::default();
+
+        for shard in &self.shards {
+            stats.read_only_commits += shard.read_only_commits.load(Ordering::Relaxed);
+            stats.write_commits += shard.write_commits.load(Ordering::Relaxed);
+            stats.reads += shard.reads.load(Ordering::Relaxed);
+            stats.writes += shard.writes.load(Ordering::Relaxed);
+            stats.promotions += shard.promotions.load(Ordering::Relaxed);
+
+            for (sum, aborts) in stats.aborts.iter_mut().zip(&shard.aborts) {
+                *sum += aborts.load(Ordering::Relaxed);
+            }
+        }
+
+        stats
+    }
+}
+
+/// The counts of the transactions run by a [`Stm`](crate::Stm) so far,
+/// got by [`Stm::stats`](crate::Stm::stats)
+///
+/// The counters are not read at the same time,
+/// so they may be a little inconsistent while transactions are running
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    read_only_commits: u64,
+    write_commits: u64,
+    reads: u64,
+    writes: u64,
+    promotions: u64,
+    aborts: [u64; REASONS],
+}
+
+impl Stats {
+    pub fn commits(&self) -> u64 {
+        self.read_only_commits + self.write_commits
+    }
+
+    /// The commits without any write
+    pub fn read_only_commits(&self) -> u64 {
+        self.read_only_commits
+    }
+
+    pub fn write_commits(&self) -> u64 {
+        self.write_commits
+    }
+
+    /// The average read set size of the committed transactions
+    pub fn average_reads(&self) -> f64 {
+        average(self.reads, self.commits())
+    }
+
+    /// The average write set size of the committed write transactions
+    pub fn average_writes(&self) -> f64 {
+        average(self.writes, self.write_commits)
+    }
+
+    /// The aborted attempts, including the user retries
+    ///
+    /// A write in read-only context only switches it to write context, it's not an abort
+    pub fn aborts(&self) -> u64 {
+        self.aborts.iter().sum()
+    }
+
+    /// The aborts of [`AbortReason::ReadValidation`]
+    pub fn read_validation_aborts(&self) -> u64 {
+        self.aborts[0]
+    }
+
+    /// The aborts of [`AbortReason::LockContention`],
+    /// i.e. failing to acquire the locks of `TVar`s
+    pub fn lock_failures(&self) -> u64 {
+        self.aborts[1]
+    }
+
+    /// The aborts of [`AbortReason::CommitValidation`]
+    pub fn commit_validation_aborts(&self) -> u64 {
+        self.aborts[2]
+    }
+
+    /// The transactions switched from read-only context to write context
+    /// after trying to write, to wait for a change or to extend the snapshot
+    pub fn promotions(&self) -> u64 {
+        self.promotions
+    }
+
+    /// The aborts of [`AbortReason::UserRetry`]
+    pub fn user_retries(&self) -> u64 {
+        self.aborts[3]
+    }
+
+    /// The aborts of [`AbortReason::Irrevocable`]
+    pub fn irrevocable_aborts(&self) -> u64 {
+        self.aborts[4]
+    }
+}
+
+fn average(sum: u64, count: u64) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        sum as f64 / count as f64
+    }
+}
@@ -1,45 +1,391 @@
-use crate::{transaction::Transaction, version_clock::VersionClock, Context};
+use std::any::type_name;
 
-pub struct Stm {
-    global_version_clock: VersionClock,
+use crate::{
+    algorithm::Algorithm, heatmap::Heatmap, stats::Counters, transaction::Transaction,
+    AbortReason, Aggressive, ConflictReport, Context, ContentionManager, Failed, Limit, Stats,
+    StmError, StmObserver, Tl2,
+};
+
+// Conflicts before a transaction runs irrevocably by default
+const IRREVOCABLE_AFTER: usize = 1000;
+
+pub struct Stm<A: Algorithm = Tl2, C: ContentionManager = Aggressive, Obs: StmObserver = ()> {
+    algorithm: A,
+    contention_manager: C,
+    irrevocable_after: usize,
+    observer: Obs,
+    counters: Counters,
+    // Some if the conflicts are tracked
+    heatmap: Option<Heatmap>,
 }
 
 impl Stm {
     pub fn new() -> Self {
+        Stm::with_algorithm(Tl2::new())
+    }
+}
+
+impl<A: Algorithm> Stm<A> {
+    /// Create a STM using the given algorithm
+    pub fn with_algorithm(algorithm: A) -> Self {
+        Stm {
+            algorithm,
+            contention_manager: Aggressive::new(),
+            irrevocable_after: IRREVOCABLE_AFTER,
+            observer: (),
+            counters: Counters::new(),
+            heatmap: None,
+        }
+    }
+}
+
+impl<A: Algorithm, C: ContentionManager, Obs: StmObserver> Stm<A, C, Obs> {
+    /// Use the given contention manager to decide what to do on conflict
+    /// ```
+    /// # use xstm::{Backoff, Stm};
+    /// let stm = Stm::new().contention_manager(Backoff::new().randomized());
+    /// ```
+    pub fn contention_manager<M: ContentionManager>(self, contention_manager: M) -> Stm<A, M, Obs> {
+        Stm {
+            algorithm: self.algorithm,
+            contention_manager,
+            irrevocable_after: self.irrevocable_after,
+            observer: self.observer,
+            counters: self.counters,
+            heatmap: self.heatmap,
+        }
+    }
+
+    /// Tell the given observer about the attempts, aborts and commits of all transactions
+    /// ```
+    /// # use xstm::{AbortReason, Stm, StmObserver};
+    /// struct Log;
+    ///
+    /// impl StmObserver for Log {
+    ///     fn on_abort(&self, reason: AbortReason) {
+    ///         eprintln!("aborted: {:?}", reason);
+    ///     }
+    /// }
+    ///
+    /// let stm = Stm::new().observer(Log);
+    /// ```
+    pub fn observer<P: StmObserver>(self, observer: P) -> Stm<A, C, P> {
         Stm {
-            global_version_clock: VersionClock::new(),
+            algorithm: self.algorithm,
+            contention_manager: self.contention_manager,
+            irrevocable_after: self.irrevocable_after,
+            observer,
+            counters: self.counters,
+            heatmap: self.heatmap,
         }
     }
 
+    /// Run a transaction [irrevocably](Stm::irrevocably) after it was aborted
+    /// by conflicts `aborts` times, so a starving transaction commits at last.
+    /// It's 1000 by default, `usize::MAX` never escalates
+    /// ```
+    /// # use xstm::Stm;
+    /// let stm = Stm::new().irrevocable_after(100);
+    /// ```
+    pub fn irrevocable_after(mut self, aborts: usize) -> Self {
+        self.irrevocable_after = aborts;
+        self
+    }
+
+    /// Count the aborts by the `TVar` causing them and the kind of transaction,
+    /// see [`conflict_report`](Stm::conflict_report)
+    ///
+    /// An abort takes a lock to be counted, it's meant for tests and benchmarks
+    pub fn track_conflicts(mut self) -> Self {
+        self.heatmap = Some(Heatmap::new());
+        self
+    }
+
+    /// The conflicts counted so far, `None` if they are not [tracked](Stm::track_conflicts)
+    /// ```
+    /// # use xstm::{Stm, TVar};
+    /// let stm = Stm::new().track_conflicts();
+    /// let balance = TVar::named("balance", 100);
+    ///
+    /// stm.atomically(balance.write(50));
+    ///
+    /// let report = stm.conflict_report().unwrap();
+    /// for var in report.top(10) {
+    ///     println!("{}: {} aborts", var.var(), var.aborts());
+    /// }
+    /// ```
+    pub fn conflict_report(&self) -> Option<ConflictReport> {
+        self.heatmap.as_ref().map(Heatmap::report)
+    }
+
+    pub fn algorithm(&self) -> &A {
+        &self.algorithm
+    }
+
+    /// The counts of commits and aborts of all transactions so far
+    /// ```
+    /// # use xstm::{Stm, TVar};
+    /// let stm = Stm::new();
+    /// let var = TVar::new(1);
+    ///
+    /// stm.atomically(var.write(2));
+    ///
+    /// let stats = stm.stats();
+    /// assert_eq!(stats.write_commits(), 1);
+    /// // Tl2 ran it in read-only context first
+    /// assert_eq!(stats.promotions(), 1);
+    /// ```
+    pub fn stats(&self) -> Stats {
+        self.counters.snapshot()
+    }
+
     pub fn atomically<T: Transaction>(&self, transaction: T) -> T::Output {
-        let mut context = Context::new(1.into());
+        match self.try_atomically(transaction) {
+            Ok(result) => result,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Run the transaction until it commits or [aborts](Context::abort)
+    ///
+    /// An aborted transaction is never committed, none of its writes are visible
+    pub fn try_atomically<E, T: Transaction<E>>(&self, transaction: T) -> Result<T::Output, E> {
+        self.run(
+            |context| transaction.atomically(context),
+            type_name::<T>(),
+            false,
+            None,
+        )
+        .map_err(Failed::into_abort)
+    }
+
+    /// Run the transaction until it commits, [aborts](Context::abort) or reaches the limit,
+    /// which is a number of attempts or an [`Instant`](std::time::Instant) deadline
+    /// ```
+    /// # use std::time::{Duration, Instant};
+    /// # use xstm::{Failed, Stm, TVar};
+    /// let stm = Stm::new();
+    /// let var = TVar::new(1);
+    ///
+    /// assert_eq!(stm.try_atomically_with(var.read(), 3), Ok(1));
+    ///
+    /// let deadline = Instant::now() + Duration::from_millis(10);
+    /// let result: Result<_, Failed> = stm.try_atomically_fn_with(
+    ///     |context| {
+    ///         let value = context.read(&var)?;
+    ///         if value < 10 {
+    ///             // wait for another thread making it 10
+    ///             return context.retry();
+    ///         }
+    ///         Ok(value)
+    ///     },
+    ///     deadline,
+    /// );
+    /// assert!(matches!(result, Err(Failed::GaveUp { .. })));
+    /// ```
+    pub fn try_atomically_with<E, T: Transaction<E>>(
+        &self,
+        transaction: T,
+        limit: impl Into<Limit>,
+    ) -> Result<T::Output, Failed<E>> {
+        self.run(
+            |context| transaction.atomically(context),
+            type_name::<T>(),
+            false,
+            Some(limit.into()),
+        )
+    }
+
+    /// Run the transaction with exclusive access, other transactions cannot commit until it ends
+    ///
+    /// The transaction is never aborted by a conflict, so it's a safe place to do I/O,
+    /// unless it calls [`retry`](Context::retry) which runs it again.
+    /// It stops every other writer, and with [`NOrec`](crate::NOrec) every reader too,
+    /// so use it sparingly, and never run another transaction inside it, which would block forever
+    /// ```
+    /// # use xstm::{Stm, TVar};
+    /// let stm = Stm::new();
+    /// let var = TVar::new(1);
+    ///
+    /// stm.irrevocably_fn(|context| {
+    ///     let value = context.read(&var)?;
+    ///     println!("runs once: {}", value);
+    ///     context.write(&var, value + 1)
+    /// });
+    ///
+    /// assert_eq!(stm.atomically(var.read()), 2);
+    /// ```
+    pub fn irrevocably<T: Transaction>(&self, transaction: T) -> T::Output {
+        let result = self.run(
+            |context| transaction.atomically(context),
+            type_name::<T>(),
+            true,
+            None,
+        );
+        match result.map_err(Failed::into_abort) {
+            Ok(result) => result,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Run a closure as [irrevocable](Stm::irrevocably) transaction
+    pub fn irrevocably_fn<'var, O, F>(&'var self, transaction: F) -> O
+    where
+        F: Fn(&mut Context<'var, A>) -> Result<O, StmError>,
+    {
+        let result = self.run(transaction, type_name::<F>(), true, None);
+        match result.map_err(Failed::into_abort) {
+            Ok(result) => result,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Run a closure as transaction
+    ///
+    /// The closure may be called many times until the transaction commits,
+    /// so it can capture the `TVar`s it uses but should not have other side effects
+    /// ```
+    /// # use xstm::{Stm, TVar};
+    /// let stm = Stm::new();
+    /// let var = TVar::new(1);
+    ///
+    /// let old = stm.atomically_fn(|context| {
+    ///     let old = context.read(&var)?;
+    ///     context.write(&var, old + 1)?;
+    ///     Ok(old)
+    /// });
+    ///
+    /// assert_eq!(old, 1);
+    /// ```
+    pub fn atomically_fn<'var, O, F>(&'var self, transaction: F) -> O
+    where
+        F: Fn(&mut Context<'var, A>) -> Result<O, StmError>,
+    {
+        let result = self.run(transaction, type_name::<F>(), false, None);
+        match result.map_err(Failed::into_abort) {
+            Ok(result) => result,
+            Err(never) => match never {},
+        }
+    }
+
+    /// Run a closure as transaction until it commits or [aborts](Context::abort)
+    pub fn try_atomically_fn<'var, O, E, F>(&'var self, transaction: F) -> Result<O, E>
+    where
+        F: Fn(&mut Context<'var, A>) -> Result<O, StmError<E>>,
+    {
+        self.run(transaction, type_name::<F>(), false, None).map_err(Failed::into_abort)
+    }
+
+    /// Run a closure as transaction until it commits, [aborts](Context::abort) or reaches the limit
+    pub fn try_atomically_fn_with<'var, O, E, F>(
+        &'var self,
+        transaction: F,
+        limit: impl Into<Limit>,
+    ) -> Result<O, Failed<E>>
+    where
+        F: Fn(&mut Context<'var, A>) -> Result<O, StmError<E>>,
+    {
+        self.run(transaction, type_name::<F>(), false, Some(limit.into()))
+    }
+
+    /// `kind` is the type name of the transaction, reported in the conflicts
+    fn run<'var, O, E, F>(
+        &'var self,
+        transaction: F,
+        kind: &'static str,
+        irrevocable: bool,
+        limit: Option<Limit>,
+    ) -> Result<O, Failed<E>>
+    where
+        F: Fn(&mut Context<'var, A>) -> Result<O, StmError<E>>,
+    {
+        let deadline = limit.and_then(|limit| limit.deadline());
+        let mut attempts = 0;
+
+        let mut context = Context::new(&self.algorithm, &self.contention_manager);
         loop {
-            let read_version = self.global_version_clock.sample();
+            // The irrevocable attempt ends with the commit, or it's dropped
+            context.end_irrevocable();
 
-            context.reset(read_version);
+            if context.switches_context() {
+                self.counters.promotion();
+            }
+            context.reset();
+
+            if irrevocable {
+                context.begin_irrevocable();
+            } else if context.aborts() >= self.irrevocable_after {
+                self.observer.on_escalation(context.aborts());
+                context.begin_irrevocable();
+            }
+
+            attempts += 1;
+            self.observer.on_attempt_start(attempts);
 
             // run transaction
-            match transaction.atomically(&mut context) {
-                Ok(result) => match context.try_commit(&self.global_version_clock) {
-                    Ok(_) => return result,
-                    #[cfg(not(feature = "retry_info"))]
-                    Err(_) => (),
-                    #[cfg(feature = "retry_info")]
-                    Err(err) => {
-                        let id = std::thread::current().id();
-                        println!("Transaction Retried in {:?}: {:?}", id, err)
+            let reason = match transaction(&mut context) {
+                Err(StmError::Abort(err)) => return Err(Failed::Abort(err)),
+                Err(StmError::Wait) => AbortReason::UserRetry,
+                Err(StmError::Retry(reason)) => reason,
+                Ok(result) => match context.try_commit() {
+                    Ok(commit) => {
+                        context.end_attempt(true);
+                        self.observer.on_commit(&commit);
+                        self.counters.commit(&commit);
+                        return Ok(result);
                     }
+                    Err(StmError::Retry(reason)) => reason,
+                    Err(StmError::Wait) => AbortReason::UserRetry,
+                    Err(StmError::Abort(never)) => match never {},
                 },
-                #[cfg(not(feature = "retry_info"))]
-                Err(_) => (),
-                #[cfg(feature = "retry_info")]
-                Err(err) => {
-                    let id = std::thread::current().id();
-                    println!("Transaction Retried in {:?}: {:?}", id, err)
-                }
+            };
+
+            // Don't hold the locks of the attempt while the contention manager delays us
+            context.abort_attempt();
+            context.end_attempt(false);
+            self.observer.on_abort(reason);
+            self.counters.abort(reason);
+
+            if let Some(heatmap) = &self.heatmap {
+                heatmap.abort(reason, kind);
+            }
+
+            if limit.is_some_and(|limit| limit.reached(attempts)) {
+                return Err(Failed::GaveUp { attempts, reason });
+            }
+
+            if reason == AbortReason::UserRetry {
+                // Others must be able to commit the change
+                context.end_irrevocable();
+                context.wait(deadline);
+            } else {
+                context.on_abort();
+            }
+
+            // The deadline may have passed while waiting
+            if limit.is_some_and(|limit| limit.reached(attempts)) {
+                return Err(Failed::GaveUp { attempts, reason });
             }
 
             // failed and retry
         }
     }
 }
+
+impl<A, C, Obs> Default for Stm<A, C, Obs>
+where
+    A: Algorithm + Default,
+    C: ContentionManager + Default,
+    Obs: StmObserver + Default,
+{
+    fn default() -> Self {
+        Stm {
+            algorithm: A::default(),
+            contention_manager: C::default(),
+            irrevocable_after: IRREVOCABLE_AFTER,
+            observer: Obs::default(),
+            counters: Counters::new(),
+            heatmap: None,
+        }
+    }
+}
@@ -0,0 +1,238 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use crate::epoch::Garbage;
+use crate::{Algorithm, Context, StmError, TVar, TVarId};
+
+// The pointer held in the TVar, the value itself is never changed
+struct Raw<T>(*const T);
+
+impl<T> Clone for Raw<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Raw<T> {}
+
+/// A transactional cell holding a heap value, such as a `String`, a `Vec` or a `HashMap`
+///
+/// A write allocates the new value and swaps the pointer on commit,
+/// a read clones the value. The replaced values are dropped once every
+/// transaction that may be reading them has finished.
+/// Unlike a `TVar`, the old values are never kept in the multi-version history,
+/// a read of a value replaced after the snapshot aborts.
+/// ```
+/// # use xstm::{Stm, TBox};
+/// let stm = Stm::new();
+/// let names = TBox::new(vec![String::from("x")]);
+///
+/// stm.atomically_fn(|context| {
+///     let mut list = context.read_box(&names)?;
+///     list.push(String::from("stm"));
+///     context.write_box(&names, list)
+/// });
+///
+/// assert_eq!(stm.atomically_fn(|context| context.read_box(&names)), ["x", "stm"]);
+/// ```
+pub struct TBox<T> {
+    var: TVar<Raw<T>>,
+    _owns: PhantomData<Box<T>>,
+}
+
+// Safety: the values are cloned by the readers and dropped by any thread
+unsafe impl<T: Send + Sync> Sync for TBox<T> {}
+unsafe impl<T: Send> Send for TBox<T> {}
+
+// The replaced values may be dropped on another thread, after any borrow ended
+impl<T: Send + 'static> TBox<T> {
+    pub fn new(value: T) -> Self {
+        TBox {
+            var: TVar::new(Raw(Box::into_raw(Box::new(value)) as *const T)).without_history(),
+            _owns: PhantomData,
+        }
+    }
+
+    /// Create a `TBox` with a name, the aborts caused by it are reported with the name
+    pub fn named(name: &'static str, value: T) -> Self {
+        TBox {
+            var: TVar::named(name, Raw(Box::into_raw(Box::new(value)) as *const T))
+                .without_history(),
+            _owns: PhantomData,
+        }
+    }
+
+    unsafe fn drop_raw(ptr: *const ()) {
+        drop(Box::from_raw(ptr as *mut T))
+    }
+}
+
+impl<T> TBox<T> {
+    pub fn name(&self) -> Option<&'static str> {
+        self.var.name()
+    }
+
+    pub fn id(&self) -> TVarId {
+        self.var.id()
+    }
+}
+
+impl<T> Drop for TBox<T> {
+    fn drop(&mut self) {
+        // Safety: no transaction is using it, the replaced values were retired already
+        unsafe { drop(Box::from_raw(self.var.value_ptr().read().0 as *mut T)) }
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for TBox<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TBox").field("name", &self.name()).finish_non_exhaustive()
+    }
+}
+
+/// A transactional cell holding an `Arc`, the reads share the value instead of cloning it
+///
+/// Same as [`TBox`] otherwise.
+/// ```
+/// # use std::sync::Arc;
+/// # use xstm::{Stm, TArc};
+/// let stm = Stm::new();
+/// let config = TArc::new(String::from("v1"));
+///
+/// stm.atomically_fn(|context| context.write_arc(&config, Arc::new(String::from("v2"))));
+///
+/// assert_eq!(*stm.atomically_fn(|context| context.read_arc(&config)), "v2");
+/// ```
+pub struct TArc<T> {
+    var: TVar<Raw<T>>,
+    _owns: PhantomData<Arc<T>>,
+}
+
+// Safety: the same as Arc<T>
+unsafe impl<T: Send + Sync> Sync for TArc<T> {}
+unsafe impl<T: Send + Sync> Send for TArc<T> {}
+
+// The same as TBox, and the readers may hold the values on any thread
+impl<T: Send + Sync + 'static> TArc<T> {
+    pub fn new(value: T) -> Self {
+        TArc {
+            var: TVar::new(Raw(Arc::into_raw(Arc::new(value)))).without_history(),
+            _owns: PhantomData,
+        }
+    }
+
+    /// Create a `TArc` with a name, the aborts caused by it are reported with the name
+    pub fn named(name: &'static str, value: T) -> Self {
+        TArc {
+            var: TVar::named(name, Raw(Arc::into_raw(Arc::new(value)))).without_history(),
+            _owns: PhantomData,
+        }
+    }
+
+    unsafe fn drop_raw(ptr: *const ()) {
+        drop(Arc::from_raw(ptr as *const T))
+    }
+}
+
+impl<T> TArc<T> {
+    pub fn name(&self) -> Option<&'static str> {
+        self.var.name()
+    }
+
+    pub fn id(&self) -> TVarId {
+        self.var.id()
+    }
+}
+
+impl<T> Drop for TArc<T> {
+    fn drop(&mut self) {
+        // Safety: no transaction is using it, the replaced values were retired already
+        unsafe { drop(Arc::from_raw(self.var.value_ptr().read().0)) }
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for TArc<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TArc").field("name", &self.name()).finish_non_exhaustive()
+    }
+}
+
+// Public methods
+impl<'var, A: Algorithm> Context<'var, A> {
+    pub fn read_box<T: Clone, E>(&mut self, tbox: &'var TBox<T>) -> Result<T, StmError<E>> {
+        let raw = self.read_raw(&tbox.var)?;
+
+        // Safety: pinned before reading the pointer, it cannot be freed until the attempt ends
+        Ok(unsafe { (*raw.0).clone() })
+    }
+
+    pub fn write_box<T: Send + 'static, E>(
+        &mut self,
+        tbox: &'var TBox<T>,
+        value: T,
+    ) -> Result<(), StmError<E>> {
+        let new = Raw(Box::into_raw(Box::new(value)) as *const T);
+        self.write_raw(&tbox.var, new, TBox::<T>::drop_raw)
+    }
+
+    pub fn read_arc<T, E>(&mut self, tarc: &'var TArc<T>) -> Result<Arc<T>, StmError<E>> {
+        let raw = self.read_raw(&tarc.var)?;
+
+        // Safety: pinned before reading the pointer, so the TArc still holds a count of it
+        unsafe {
+            Arc::increment_strong_count(raw.0);
+            Ok(Arc::from_raw(raw.0))
+        }
+    }
+
+    pub fn write_arc<T: Send + Sync + 'static, E>(
+        &mut self,
+        tarc: &'var TArc<T>,
+        value: Arc<T>,
+    ) -> Result<(), StmError<E>> {
+        let new = Raw(Arc::into_raw(value));
+        self.write_raw(&tarc.var, new, TArc::<T>::drop_raw)
+    }
+}
+
+// Internal methods
+impl<'var, A: Algorithm> Context<'var, A> {
+    fn read_raw<T, E>(&mut self, var: &'var TVar<Raw<T>>) -> Result<Raw<T>, StmError<E>> {
+        // The replaced pointers are retired after the pin
+        self.reclaim().pin();
+        self.read(var)
+    }
+
+    fn write_raw<T, E>(
+        &mut self,
+        var: &'var TVar<Raw<T>>,
+        new: Raw<T>,
+        drop_raw: unsafe fn(*const ()),
+    ) -> Result<(), StmError<E>> {
+        // The commit validates the read, so it's the pointer replaced on commit
+        let old = match self.read_raw(var) {
+            Ok(old) => old,
+            Err(err) => {
+                unsafe { drop_raw(new.0 as *const ()) };
+                return Err(err);
+            }
+        };
+
+        if let Err(err) = self.write(var, new) {
+            // Never published
+            unsafe { drop_raw(new.0 as *const ()) };
+            return Err(err);
+        }
+
+        // Safety: the value is owned by the TVar, or by the transaction until it commits
+        let (old, new) = unsafe {
+            (
+                Garbage::new(old.0 as *const (), drop_raw),
+                Garbage::new(new.0 as *const (), drop_raw),
+            )
+        };
+        self.reclaim().replace(old, new);
+
+        Ok(())
+    }
+}
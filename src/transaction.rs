@@ -1,23 +1,30 @@
 mod ext;
+mod or_else;
 
-// pub use ext::*;
+pub use ext::TransactionExt;
+pub use or_else::OrElse;
 
-use crate::{Context, StmError};
+use std::convert::Infallible;
 
-pub trait Transaction {
+use crate::{Algorithm, Context, StmError};
+
+/// A transaction that can be run by [`Stm`](crate::Stm)
+///
+/// The `TVar`s used in the transaction must live as long as `&self`,
+/// so the transaction can just store references (or `Arc`s) to them.
+/// `E` is the error type the transaction can [`abort`](Context::abort) with.
+/// The transaction is generic over the [`Algorithm`] of `Stm`,
+/// so it can be run on any of them.
+///
+/// The signature changed in 0.2.0, the implementations written for the old one
+/// `fn atomically<'this: 'var, 'context, 'var>(&'this self, context: &'context mut Context<'var>)`
+/// don't compile any more. They only need to rename `'this` to `'var`, drop `'context`
+/// and add the `A: Algorithm` parameter, the bodies stay the same.
+pub trait Transaction<E = Infallible> {
     type Output;
 
-    fn atomically<'this: 'var, 'context, 'var>(
-        &'this self,
-        context: &'context mut Context<'var>,
-    ) -> Result<Self::Output, StmError>;
+    fn atomically<'var, A: Algorithm>(
+        &'var self,
+        context: &mut Context<'var, A>,
+    ) -> Result<Self::Output, StmError<E>>;
 }
-
-// how to forbid (|trans| Ok(trans) )
-
-// pub trait NewTransaction {
-//     type Output;
-//     type Context<'var>;
-
-//     fn atomically<'var>(&self, context: Self::Context<'var>) -> Result<Self::Output, StmError>;
-// }
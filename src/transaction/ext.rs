@@ -1,6 +1,19 @@
+use std::convert::Infallible;
 
-// pub trait TransactionExt : Transaction {
-//     fn or_else(self, other: )
-// }
+use super::{OrElse, Transaction};
 
-// impl<T: Transaction> TransactionExt for T {}
\ No newline at end of file
+pub trait TransactionExt<E = Infallible>: Transaction<E> + Sized {
+    /// Run `self`, if it calls [`Context::retry`](crate::Context::retry),
+    /// discard its writes and run `other` in the same transaction instead.
+    ///
+    /// If both of them retry,
+    /// the transaction will wait until any `TVar` read by either of them was changed
+    fn or_else<B>(self, other: B) -> OrElse<Self, B>
+    where
+        B: Transaction<E, Output = Self::Output>,
+    {
+        OrElse::new(self, other)
+    }
+}
+
+impl<E, T: Transaction<E>> TransactionExt<E> for T {}
@@ -0,0 +1,43 @@
+use crate::{Algorithm, Context, StmError, Transaction};
+
+/// The transaction created by [`TransactionExt::or_else`](crate::TransactionExt::or_else)
+pub struct OrElse<A, B> {
+    first: A,
+    second: B,
+}
+
+impl<A, B> OrElse<A, B> {
+    pub(crate) fn new(first: A, second: B) -> Self {
+        OrElse { first, second }
+    }
+}
+
+impl<E, First, Second> Transaction<E> for OrElse<First, Second>
+where
+    First: Transaction<E>,
+    Second: Transaction<E, Output = First::Output>,
+{
+    type Output = First::Output;
+
+    fn atomically<'var, A: Algorithm>(
+        &'var self,
+        context: &mut Context<'var, A>,
+    ) -> Result<Self::Output, StmError<E>> {
+        let checkpoint = context.checkpoint();
+
+        match self.first.atomically(context) {
+            Err(StmError::Wait) => {
+                // Only discard the writes of first
+                // Its reads are kept, so we can wait on both read sets if second retries too
+                context.rollback(checkpoint);
+
+                self.second.atomically(context)
+            }
+            result => {
+                context.release(checkpoint);
+
+                result
+            }
+        }
+    }
+}
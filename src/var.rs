@@ -1,25 +1,100 @@
+use crate::atomic_bytes;
+use crate::history::History;
+use crate::meta::{LazyMeta, Meta};
 use crate::version::Version;
+use crate::version_clock::VersionClock;
 use crate::versioned_lock::VersionedLock;
-use crate::Transaction;
-use std::cell::Cell;
-use std::fmt::Debug;
+use crate::{SafeRead, Transaction};
+use std::cell::UnsafeCell;
+use std::fmt::{Debug, Display};
+use std::mem::MaybeUninit;
+
+/// The identity of a [`TVar`], unique while the `TVar` is alive
+///
+/// It's shown as the name of the `TVar` if it was [named](TVar::named),
+/// or the address otherwise
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord, Hash)]
+pub struct TVarId {
+    ptr: usize,
+    name: Option<&'static str>,
+}
+
+impl TVarId {
+    pub(crate) fn new(ptr: *const (), name: Option<&'static str>) -> Self {
+        TVarId {
+            ptr: ptr as usize,
+            name,
+        }
+    }
+
+    pub fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+
+    pub(crate) fn addr(&self) -> usize {
+        self.ptr
+    }
+}
+
+impl Display for TVarId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.name {
+            Some(name) => f.write_str(name),
+            None => write!(f, "TVar@{:#x}", self.ptr),
+        }
+    }
+}
+
+// The readers copy the value while a writer may be storing it,
+// it's aligned for the word-sized atomic copies.
+// A move of MaybeUninit keeps every byte, a move of T would lose the frozen padding
+#[repr(C, align(8))]
+struct Storage<T>(UnsafeCell<MaybeUninit<T>>);
+
+const _: () = assert!(std::mem::align_of::<Storage<u8>>() == atomic_bytes::ALIGN);
 
 pub struct TVar<T> {
-    value: Cell<T>,
+    value: Storage<T>,
     versioned_lock: VersionedLock,
+    // the waiters, history and name, allocated on the first use
+    meta: LazyMeta,
 }
 
 // We can only Read/Write TVar in transaction
-// TVar can be Sync safely
-unsafe impl<T: Copy> Sync for TVar<T> {}
+// The copies are handed to other threads and used there, so T must be Send + Sync too
+unsafe impl<T: SafeRead + Send + Sync> Sync for TVar<T> {}
 
 // Public method
-impl<T: Copy> TVar<T> {
+impl<T: SafeRead> TVar<T> {
     pub fn new(value: T) -> Self {
-        TVar {
-            value: Cell::new(value),
+        let var = TVar {
+            value: Storage(UnsafeCell::new(MaybeUninit::uninit())),
             versioned_lock: VersionedLock::new(),
-        }
+            meta: LazyMeta::new(),
+        };
+
+        // Safety: not shared yet, the padding is frozen by the copy
+        unsafe { var.write_in_place(value) };
+        var
+    }
+
+    /// Create a `TVar` with a name, the aborts caused by it are reported with the name
+    /// ```
+    /// # use xstm::TVar;
+    /// let balance = TVar::named("balance", 100);
+    /// assert_eq!(balance.id().to_string(), "balance");
+    /// ```
+    pub fn named(name: &'static str, value: T) -> Self {
+        let mut var = TVar::new(value);
+        var.meta = LazyMeta::with(Meta {
+            name: Some(name),
+            ..Meta::new()
+        });
+        var
+    }
+
+    pub fn name(&self) -> Option<&'static str> {
+        self.meta.name()
     }
 
     pub fn read(&self) -> impl Transaction<Output = T> + '_ {
@@ -29,20 +104,24 @@ impl<T: Copy> TVar<T> {
     pub fn write(&self, value: T) -> impl Transaction<Output = ()> + '_ {
         WriteTransaction { var: self, value }
     }
+
+    pub fn id(&self) -> TVarId {
+        TVarId::new(self.value_ptr() as *const (), self.name())
+    }
 }
 
 struct ReadTransaction<'var, T> {
     var: &'var TVar<T>,
 }
 
-impl<'trans_var, T: Copy> Transaction for ReadTransaction<'trans_var, T> {
+impl<'trans_var, T: SafeRead> Transaction for ReadTransaction<'trans_var, T> {
     type Output = T;
 
-    fn atomically<'this: 'var, 'context, 'var>(
-        &'this self,
-        context: &'context mut crate::Context<'var>,
+    fn atomically<'var, A: crate::Algorithm>(
+        &'var self,
+        context: &mut crate::Context<'var, A>,
     ) -> Result<Self::Output, crate::StmError> {
-        context.read(&self.var)
+        context.read(self.var)
     }
 }
 
@@ -51,55 +130,194 @@ struct WriteTransaction<'var, T> {
     value: T,
 }
 
-impl<'trans_var, T: Copy> Transaction for WriteTransaction<'trans_var, T> {
+impl<'trans_var, T: SafeRead> Transaction for WriteTransaction<'trans_var, T> {
     type Output = ();
 
-    fn atomically<'this: 'var, 'context, 'var>(
-        &'this self,
-        context: &'context mut crate::Context<'var>,
+    fn atomically<'var, A: crate::Algorithm>(
+        &'var self,
+        context: &mut crate::Context<'var, A>,
     ) -> Result<Self::Output, crate::StmError> {
-        context.write(&self.var, self.value)
+        context.write(self.var, self.value.clone())
     }
 }
 
 // internal methods
-impl<T: Copy> TVar<T> {
+impl<T: SafeRead> TVar<T> {
+    /// Never keep the old values, they may be freed once replaced
+    pub(crate) fn without_history(mut self) -> Self {
+        self.meta = LazyMeta::with(Meta {
+            history: History::disabled(),
+            name: self.name(),
+            ..Meta::new()
+        });
+        self
+    }
+
     pub(crate) fn value_ptr(&self) -> *const T {
-        self.value.as_ptr()
+        self.value.0.get() as *const T
     }
 
     pub(crate) fn get_lock(&self) -> &'_ VersionedLock {
         &self.versioned_lock
     }
 
+    pub(crate) fn get_meta(&self) -> &'_ LazyMeta {
+        &self.meta
+    }
+
+    /// Copy the data without any validation
+    /// It may be torn by a concurrent writer, so it cannot be used before validating
+    pub(crate) fn read_unchecked(&self) -> MaybeUninit<T> {
+        let mut data = MaybeUninit::<T>::uninit();
+        unsafe { self.read_unchecked_into(data.as_mut_ptr()) };
+        data
+    }
+
+    /// Safety: `dst` is valid for writes
+    pub(crate) unsafe fn read_unchecked_into(&self, dst: *mut T) {
+        // copy the bytes, the padding was frozen by the writers
+        atomic_bytes::load(
+            self.value_ptr() as *const u8,
+            dst as *mut u8,
+            std::mem::size_of::<T>(),
+        );
+    }
+
+    /// Store the value in place
+    /// Safety: the TVar is locked by the caller
+    pub(crate) unsafe fn write_in_place(&self, value: T) {
+        atomic_bytes::store(
+            &value as *const T as *const u8,
+            self.value_ptr() as *mut u8,
+            std::mem::size_of::<T>(),
+        );
+    }
+
     pub(crate) fn read_with_check(&self, read_version: Version) -> Option<T> {
+        // Safety: no writer changed the data while copying
+        self.copy_with_check(read_version)
+            .map(|data| unsafe { data.assume_init() })
+    }
+
+    /// Copy the data once and run `f` on the copy after validating it
+    /// Give `f` back if the TVar is newer than read_version or was changed meanwhile
+    pub(crate) fn inspect_with_check<R, F: FnOnce(&T) -> R>(
+        &self,
+        read_version: Version,
+        f: F,
+    ) -> Result<R, F> {
+        match self.copy_with_check(read_version) {
+            // Safety: no writer changed the data while copying
+            Some(data) => Ok(f(unsafe { data.assume_init_ref() })),
+            None => Err(f),
+        }
+    }
+
+    fn copy_with_check(&self, read_version: Version) -> Option<MaybeUninit<T>> {
+        let mut data = MaybeUninit::<T>::uninit();
+
+        unsafe { self.read_into_with_check(read_version, data.as_mut_ptr()) }.then_some(data)
+    }
+
+    /// Copy the data to `dst`, return false if it may be torn
+    /// Safety: `dst` is valid for writes
+    pub(crate) unsafe fn read_into_with_check(&self, read_version: Version, dst: *mut T) -> bool {
         // Pre-Validation
         let pre_version = self.versioned_lock.version();
 
         if !pre_version.check(read_version) {
-            return None;
+            return false;
         }
 
-        // read the data
-        let data = self.value.get();
+        // read the data, it may be torn until checked
+        self.read_unchecked_into(dst);
 
         // Post-Validation
         let post_version = self.versioned_lock.version();
 
         // check the data was not changed
-        if post_version != pre_version {
+        post_version == pre_version
+    }
+
+    /// Wait until the TVar is unlocked and read the latest committed value,
+    /// whatever its version is
+    pub(crate) fn read_latest(&self) -> T {
+        // Safety: no writer changed the data while copying
+        unsafe { self.copy_latest().assume_init() }
+    }
+
+    /// Run `f` on the latest committed value like [`read_latest`](Self::read_latest)
+    pub(crate) fn inspect_latest<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        // Safety: no writer changed the data while copying
+        f(unsafe { self.copy_latest().assume_init_ref() })
+    }
+
+    /// Run `f` on the latest committed value in place, once the TVar is unlocked
+    /// Safety: no transaction can write the TVar after it's seen unlocked until `f` returns,
+    /// e.g. the caller is irrevocable and the writers only write after ticking the clock
+    pub(crate) unsafe fn inspect_in_place<R>(&self, f: impl FnOnce(&T) -> R) -> R {
+        while self.versioned_lock.version().is_locked() {
+            // The holder is committing or will abort
+            std::thread::yield_now();
+        }
+
+        f(&*self.value_ptr())
+    }
+
+    fn copy_latest(&self) -> MaybeUninit<T> {
+        let mut data = MaybeUninit::<T>::uninit();
+        unsafe { self.read_latest_into(data.as_mut_ptr()) };
+        data
+    }
+
+    /// Copy the latest committed value to `dst` like [`read_latest`](Self::read_latest)
+    /// Safety: `dst` is valid for writes
+    pub(crate) unsafe fn read_latest_into(&self, dst: *mut T) {
+        while !self.read_into_with_check(isize::MAX.into(), dst) {
+            // The holder is committing or will abort
+            std::thread::yield_now();
+        }
+    }
+
+    /// Read the value valid at read_version from the history
+    /// if the current one is newer
+    pub(crate) fn read_from_history(&self, read_version: Version, clock: &VersionClock) -> Option<T> {
+        let mut data = MaybeUninit::<T>::uninit();
+
+        let bytes = unsafe {
+            std::slice::from_raw_parts_mut(data.as_mut_ptr() as *mut u8, std::mem::size_of::<T>())
+        };
+
+        let written_after = |version| clock.written_after(read_version, version);
+
+        let Some(meta) = self.meta.get() else {
+            // Never recorded
             return None;
+        };
+
+        if meta.history.read(read_version, written_after, bytes) {
+            // Safety: the bytes were copied from a committed value
+            Some(unsafe { data.assume_init() })
+        } else {
+            None
         }
+    }
+}
 
-        Some(data)
+impl<T> Drop for TVar<T> {
+    fn drop(&mut self) {
+        // Safety: written in new, only replaced by other values since
+        unsafe { self.value.0.get_mut().assume_init_drop() }
     }
 }
 
-impl<T: Debug + Copy> Debug for TVar<T> {
+impl<T: Debug + SafeRead> Debug for TVar<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("TVar")
-            .field("value", &self.value)
+            .field("name", &self.name())
+            .field("value", &self.read_with_check(isize::MAX.into()))
             .field("versioned_lock", &self.versioned_lock)
+            .field("meta", &self.meta.get())
             .finish()
     }
 }
@@ -24,9 +24,9 @@ impl From<isize> for Version {
     }
 }
 
-impl Into<isize> for Version {
-    fn into(self) -> isize {
-        self.version
+impl From<Version> for isize {
+    fn from(value: Version) -> Self {
+        value.version
     }
 }
 
@@ -1,25 +1,291 @@
 use crate::version::Version;
-use std::sync::atomic::{AtomicIsize, Ordering};
+use std::{
+    cell::Cell,
+    sync::atomic::{AtomicIsize, AtomicUsize, Ordering},
+};
+
+/// How the global version clock of [`Tl2`](crate::Tl2) is advanced by the committing transactions
+///
+/// The strategies from the TL2 paper, they trade the contention on the clock
+/// for the read-set validations and aborts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum ClockStrategy {
+    /// Every commit increments the clock (`fetch_add`)
+    #[default]
+    Gv1,
+    /// Every commit tries to increment the clock once (CAS),
+    /// when it fails the version written by the winner is shared.
+    /// Fewer retries on the clock, but the read set is validated more often
+    Gv4,
+    /// The commits never increment the clock, they write `clock + 1`.
+    /// The clock is only advanced by the transactions reading a too new version,
+    /// it almost removes the contention on the clock but aborts more
+    Gv5,
+    /// Like [`Gv5`](ClockStrategy::Gv5),
+    /// but every 32nd commit of a thread increments the clock like [`Gv4`](ClockStrategy::Gv4)
+    Gv6,
+    /// Every thread increments its own clock in a vector of clocks,
+    /// the version is the sum of them.
+    /// The commits never contend on the clock, but a sample reads the whole vector
+    /// and the read set is always validated
+    PerThread,
+}
 
 pub struct VersionClock {
     version: AtomicIsize,
+    strategy: ClockStrategy,
+    // only used by PerThread, added to version
+    thread_clocks: Box<[ThreadClock]>,
+}
+
+// One cache line each, so the commits of different threads don't contend
+#[derive(Default)]
+#[repr(align(64))]
+struct ThreadClock(AtomicIsize);
+
+// Set while an irrevocable transaction is running, the other commits must fail
+const IRREVOCABLE: isize = 1 << (isize::BITS - 2);
+
+/// The version of a committing transaction
+pub struct Tick {
+    pub write_version: Version,
+    // Nobody committed since read_version, the read set doesn't need to be validated
+    pub exclusive: bool,
+}
+
+// Commits in GV6 between two increments
+const GV6_PERIOD: usize = 32;
+
+// The next thread to get a clock in the vector
+static NEXT_THREAD: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    static GV6_COMMITS: Cell<usize> = const { Cell::new(0) };
+    static THREAD_INDEX: usize = NEXT_THREAD.fetch_add(1, Ordering::Relaxed);
 }
 
 impl VersionClock {
     pub fn new() -> VersionClock {
+        VersionClock::with_strategy(ClockStrategy::Gv1)
+    }
+
+    pub fn with_strategy(strategy: ClockStrategy) -> VersionClock {
+        let thread_clocks = match strategy {
+            // More threads than cores share the clocks
+            ClockStrategy::PerThread => std::thread::available_parallelism()
+                .map_or(1, usize::from)
+                .next_power_of_two(),
+            _ => 0,
+        };
+
         VersionClock {
             version: AtomicIsize::new(1),
+            strategy,
+            thread_clocks: (0..thread_clocks).map(|_| ThreadClock::default()).collect(),
         }
     }
 
     pub fn sample(&self) -> Version {
         let version = self.version.load(Ordering::SeqCst);
-        version.into()
+        self.with_thread_clocks(version & !IRREVOCABLE).into()
+    }
+
+    // Every clock only grows, a sum read after an increment is larger
+    // than every sum read before it, like a single clock
+    fn with_thread_clocks(&self, version: isize) -> isize {
+        self.thread_clocks
+            .iter()
+            .fold(version, |sum, clock| {
+                sum.wrapping_add(clock.0.load(Ordering::SeqCst))
+            })
+    }
+
+    /// Sample after the running irrevocable transaction finished
+    pub fn sample_unlocked(&self) -> Version {
+        self.with_thread_clocks(self.wait_unlocked()).into()
+    }
+
+    // The global clock without the thread clocks
+    fn wait_unlocked(&self) -> isize {
+        loop {
+            let version = self.version.load(Ordering::SeqCst);
+
+            if version & IRREVOCABLE == 0 {
+                return version;
+            }
+
+            std::thread::yield_now();
+        }
+    }
+
+    /// Stop the other transactions committing until `unlock`
+    /// Only one irrevocable transaction can run at a time
+    pub fn lock(&self) {
+        loop {
+            let version = self.wait_unlocked();
+
+            if self
+                .version
+                .compare_exchange(
+                    version,
+                    version | IRREVOCABLE,
+                    Ordering::SeqCst,
+                    Ordering::SeqCst,
+                )
+                .is_ok()
+            {
+                return;
+            }
+        }
+    }
+
+    pub fn unlock(&self) {
+        self.version.fetch_and(!IRREVOCABLE, Ordering::SeqCst);
     }
 
-    pub fn tick(&self) -> Version {
-        // add and fetch
+    /// Get the write version of the irrevocable transaction holding the lock
+    pub fn tick_irrevocable(&self) -> Version {
         let old = self.version.fetch_add(1, Ordering::SeqCst);
-        old.wrapping_add(1).into()
+        self.with_thread_clocks((old & !IRREVOCABLE).wrapping_add(1)).into()
+    }
+
+    /// Sample a version newer than read_version
+    /// The lazy strategies don't advance the clock when committing,
+    /// advance it for them
+    pub fn sample_newer(&self, read_version: Version) -> Version {
+        if matches!(self.strategy, ClockStrategy::Gv5 | ClockStrategy::Gv6) {
+            // It's enough that anyone advanced it
+            let _ = self.increment(read_version.into());
+        }
+
+        self.sample()
+    }
+
+    /// Whether the version was surely written after read_version was sampled
+    pub fn written_after(&self, read_version: Version, version: Version) -> bool {
+        match self.strategy {
+            ClockStrategy::Gv1 | ClockStrategy::Gv4 | ClockStrategy::PerThread => {
+                version > read_version
+            }
+            // read_version + 1 may have been written before sampling
+            ClockStrategy::Gv5 | ClockStrategy::Gv6 => version > read_version + 1,
+        }
+    }
+
+    /// Get a version newer than all the written ones,
+    /// and make it visible to the new snapshots
+    pub fn advance(&self) -> Version {
+        // The lazy strategies may have written clock + 1 already
+        let step = match self.strategy {
+            ClockStrategy::Gv5 | ClockStrategy::Gv6 => 2,
+            ClockStrategy::Gv1 | ClockStrategy::Gv4 | ClockStrategy::PerThread => 1,
+        };
+
+        // The lock of irrevocable transaction is kept
+        let old = self.version.fetch_add(step, Ordering::SeqCst);
+        self.with_thread_clocks((old & !IRREVOCABLE).wrapping_add(step)).into()
+    }
+
+    /// Get the write version of a committing transaction
+    /// None if an irrevocable transaction is running
+    pub fn tick(&self, read_version: Version) -> Option<Tick> {
+        match self.strategy {
+            ClockStrategy::Gv1 => {
+                // add and fetch
+                let old = self.version.fetch_add(1, Ordering::SeqCst);
+
+                if old & IRREVOCABLE != 0 {
+                    return None;
+                }
+
+                let write_version = Version::from(old.wrapping_add(1));
+
+                Some(Tick {
+                    write_version,
+                    exclusive: write_version == read_version + 1,
+                })
+            }
+            ClockStrategy::Gv4 => self.pass_on_failure(read_version),
+            ClockStrategy::Gv5 => self.lazy(),
+            ClockStrategy::Gv6 => {
+                let commits = GV6_COMMITS.with(|commits| {
+                    commits.set(commits.get().wrapping_add(1));
+                    commits.get()
+                });
+
+                if commits.is_multiple_of(GV6_PERIOD) {
+                    self.pass_on_failure(read_version)
+                } else {
+                    self.lazy()
+                }
+            }
+            ClockStrategy::PerThread => self.per_thread(),
+        }
+    }
+
+    fn increment(&self, current: isize) -> Result<isize, isize> {
+        self.version.compare_exchange(
+            current,
+            current.wrapping_add(1),
+            Ordering::SeqCst,
+            Ordering::SeqCst,
+        )
+    }
+
+    fn pass_on_failure(&self, read_version: Version) -> Option<Tick> {
+        let current = self.version.load(Ordering::SeqCst);
+
+        if current & IRREVOCABLE != 0 {
+            return None;
+        }
+
+        match self.increment(current) {
+            Ok(_) => {
+                let write_version = Version::from(current.wrapping_add(1));
+
+                Some(Tick {
+                    write_version,
+                    exclusive: write_version == read_version + 1,
+                })
+            }
+            // The irrevocable transaction started
+            Err(winner) if winner & IRREVOCABLE != 0 => None,
+            // Use the version of the winner
+            // It may have written the TVars we read
+            Err(winner) => Some(Tick {
+                write_version: winner.into(),
+                exclusive: false,
+            }),
+        }
+    }
+
+    fn per_thread(&self) -> Option<Tick> {
+        if self.version.load(Ordering::SeqCst) & IRREVOCABLE != 0 {
+            return None;
+        }
+
+        let index = THREAD_INDEX.with(|index| *index) % self.thread_clocks.len();
+        self.thread_clocks[index].0.fetch_add(1, Ordering::SeqCst);
+
+        // The other threads may have committed with the same version,
+        // a sample of the whole vector can't tell
+        Some(Tick {
+            write_version: self.sample(),
+            exclusive: false,
+        })
+    }
+
+    fn lazy(&self) -> Option<Tick> {
+        // Other transactions may get the same version
+        let current = self.version.load(Ordering::SeqCst);
+
+        if current & IRREVOCABLE != 0 {
+            return None;
+        }
+
+        Some(Tick {
+            write_version: current.wrapping_add(1).into(),
+            exclusive: false,
+        })
     }
 }
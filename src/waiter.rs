@@ -0,0 +1,97 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Condvar, Mutex,
+    },
+    time::Instant,
+};
+
+/// A blocked transaction waiting for some TVars to be changed
+#[derive(Debug)]
+pub struct Waiter {
+    notified: Mutex<bool>,
+    condvar: Condvar,
+}
+
+impl Waiter {
+    pub fn new() -> Arc<Waiter> {
+        Arc::new(Waiter {
+            notified: Mutex::new(false),
+            condvar: Condvar::new(),
+        })
+    }
+
+    /// Wake up the blocked thread
+    pub fn notify(&self) {
+        let mut notified = self.notified.lock().unwrap_or_else(|err| err.into_inner());
+        *notified = true;
+        self.condvar.notify_one();
+    }
+
+    /// Block the current thread until `notify` was called or the deadline passed
+    pub fn wait(&self, deadline: Option<Instant>) {
+        let mut notified = self.notified.lock().unwrap_or_else(|err| err.into_inner());
+        while !*notified {
+            notified = match deadline {
+                None => self
+                    .condvar
+                    .wait(notified)
+                    .unwrap_or_else(|err| err.into_inner()),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return;
+                    }
+
+                    self.condvar
+                        .wait_timeout(notified, deadline - now)
+                        .unwrap_or_else(|err| err.into_inner())
+                        .0
+                }
+            };
+        }
+    }
+}
+
+/// All waiters blocked on a TVar
+#[derive(Debug)]
+pub struct WaitList {
+    // the length of waiters
+    // lets the committing transaction skip the mutex when nobody is waiting
+    count: AtomicUsize,
+    waiters: Mutex<Vec<Arc<Waiter>>>,
+}
+
+impl WaitList {
+    pub fn new() -> WaitList {
+        WaitList {
+            count: AtomicUsize::new(0),
+            waiters: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn register(&self, waiter: &Arc<Waiter>) {
+        let mut waiters = self.waiters.lock().unwrap_or_else(|err| err.into_inner());
+        waiters.push(waiter.clone());
+        self.count.store(waiters.len(), Ordering::SeqCst);
+    }
+
+    pub fn unregister(&self, waiter: &Arc<Waiter>) {
+        let mut waiters = self.waiters.lock().unwrap_or_else(|err| err.into_inner());
+        waiters.retain(|registered| !Arc::ptr_eq(registered, waiter));
+        self.count.store(waiters.len(), Ordering::SeqCst);
+    }
+
+    /// Wake up and remove all waiters
+    pub fn notify_all(&self) {
+        if self.count.load(Ordering::SeqCst) == 0 {
+            return;
+        }
+
+        let mut waiters = self.waiters.lock().unwrap_or_else(|err| err.into_inner());
+        for waiter in waiters.drain(..) {
+            waiter.notify();
+        }
+        self.count.store(0, Ordering::SeqCst);
+    }
+}
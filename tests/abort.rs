@@ -0,0 +1,61 @@
+use xstm::{Algorithm, Context, Stm, StmError, TVar, Transaction};
+
+#[derive(Debug, PartialEq)]
+struct InsufficientFunds {
+    balance: u64,
+}
+
+struct Transfer<'a> {
+    from: &'a TVar<u64>,
+    to: &'a TVar<u64>,
+    amount: u64,
+}
+
+impl<'a> Transaction<InsufficientFunds> for Transfer<'a> {
+    type Output = ();
+
+    fn atomically<'var, A: Algorithm>(
+        &'var self,
+        context: &mut Context<'var, A>,
+    ) -> Result<Self::Output, StmError<InsufficientFunds>> {
+        let to = context.read(self.to)?;
+        context.write(self.to, to + self.amount)?;
+
+        let from = context.read(self.from)?;
+        if from < self.amount {
+            return context.abort(InsufficientFunds { balance: from });
+        }
+        context.write(self.from, from - self.amount)?;
+
+        Ok(())
+    }
+}
+
+#[test]
+fn basic() {
+    let stm = Stm::new();
+
+    let from = TVar::new(100);
+    let to = TVar::new(0);
+
+    let transfer = Transfer {
+        from: &from,
+        to: &to,
+        amount: 60,
+    };
+    assert_eq!(stm.try_atomically(transfer), Ok(()));
+
+    let transfer = Transfer {
+        from: &from,
+        to: &to,
+        amount: 60,
+    };
+    assert_eq!(
+        stm.try_atomically(transfer),
+        Err(InsufficientFunds { balance: 40 })
+    );
+
+    // the aborted transaction wrote nothing
+    assert_eq!(stm.atomically(from.read()), 40);
+    assert_eq!(stm.atomically(to.read()), 60);
+}
@@ -0,0 +1,57 @@
+mod common;
+
+use common::with_conflicts;
+use xstm::{AbortReason, Failed, NOrec, Stm, TVar};
+
+#[test]
+fn write_in_read_only() {
+    let stm = Stm::new();
+    let var = TVar::new(0);
+
+    // Tl2 runs a transaction in read-only context first
+    let result = stm.try_atomically_with(var.write(1), 1);
+    assert_eq!(
+        result,
+        Err(Failed::GaveUp {
+            attempts: 1,
+            reason: AbortReason::WriteInReadOnly
+        })
+    );
+
+    assert_eq!(stm.atomically(var.read()), 0);
+}
+
+#[test]
+fn read_validation() {
+    let stm = Stm::with_algorithm(NOrec::new());
+    let a = TVar::new(0);
+    let b = TVar::new(0);
+
+    let increment = || {
+        stm.atomically_fn(|context| {
+            let x = context.read(&a)?;
+            context.write(&a, x + 1)
+        })
+    };
+
+    let result: Result<(), Failed> = with_conflicts(increment, |conflict| {
+        stm.try_atomically_fn_with(
+            |context| {
+                context.read(&a)?;
+                // change the read TVar before the next read
+                conflict();
+                context.read(&b)?;
+                Ok(())
+            },
+            1,
+        )
+    });
+
+    assert_eq!(
+        result,
+        Err(Failed::GaveUp {
+            attempts: 1,
+            reason: AbortReason::ReadValidation { var: Some(a.id()) }
+        })
+    );
+}
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use xstm::{Context, Stm, StmError, TVar, Transaction};
+use xstm::{Algorithm, Context, Stm, StmError, TVar, Transaction};
 
 type BigArray = TVar<[u32; 10086]>;
 
@@ -11,29 +11,28 @@ struct Update {
 impl Transaction for Update {
     type Output = u32;
 
-    fn atomically<'this: 'var, 'context, 'var>(
-        &'this self,
-        context: &'context mut Context<'var>,
+    fn atomically<'var, A: Algorithm>(
+        &'var self,
+        context: &mut Context<'var, A>,
     ) -> Result<Self::Output, StmError> {
-        let mut array = context.read(&self.array)?;
-
-        for (_ , window) in array.windows(2).enumerate() {
-            let first = window[0];
-            let second = window[1];
-
-            if second != first + 1 {
-                let tid = std::thread::current().id();
-                panic!("{:?} Bad Input: {:?}", tid,window);
+        // Changed in the write buffer
+        context.update(&self.array, |array| {
+            for window in array.windows(2) {
+                let first = window[0];
+                let second = window[1];
+
+                if second != first + 1 {
+                    let tid = std::thread::current().id();
+                    panic!("{:?} Bad Input: {:?}", tid,window);
+                }
             }
-        }
 
-        for i in &mut array {
-            *i += 1
-        }
-
-        context.write(&self.array, array)?;
+            for i in array.iter_mut() {
+                *i += 1
+            }
 
-        Ok(array[0])
+            array[0]
+        })
     }
 }
 
@@ -48,7 +47,7 @@ async fn basic() {
 
     let array = Arc::new(TVar::new(array));
 
-    let count = 100_00;
+    let count = 10_000;
 
     let mut handles = Vec::new();
     for _ in 0..count {
@@ -0,0 +1,19 @@
+mod common;
+
+use common::counter;
+use xstm::{ClockStrategy, Stm, Tl2};
+
+#[test]
+fn strategies() {
+    for strategy in [
+        ClockStrategy::Gv1,
+        ClockStrategy::Gv4,
+        ClockStrategy::Gv5,
+        ClockStrategy::Gv6,
+        ClockStrategy::PerThread,
+    ] {
+        counter(Stm::with_algorithm(Tl2::new().clock(strategy)));
+        counter(Stm::with_algorithm(Tl2::new().clock(strategy).eager()));
+        counter(Stm::with_algorithm(Tl2::new().clock(strategy).multi_version(4)));
+    }
+}
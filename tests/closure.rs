@@ -0,0 +1,57 @@
+use xstm::{Stm, TVar};
+
+#[derive(Debug, PartialEq)]
+struct Empty;
+
+#[test]
+fn basic() {
+    let stm = Stm::new();
+
+    let var_a = TVar::new(0);
+    let var_b = TVar::new(0);
+
+    let thread_count = 8;
+    let repeat_count = 1000;
+
+    std::thread::scope(|scope| {
+        for _ in 0..thread_count {
+            scope.spawn(|| {
+                for _ in 0..repeat_count {
+                    stm.atomically_fn(|context| {
+                        let a = context.read(&var_a)?;
+                        let b = context.read(&var_b)?;
+
+                        context.write(&var_a, a + 1)?;
+                        context.write(&var_b, b + a)
+                    });
+                }
+            });
+        }
+    });
+
+    let count = thread_count * repeat_count;
+    let (a, b) = stm.atomically_fn(|context| Ok((context.read(&var_a)?, context.read(&var_b)?)));
+
+    assert_eq!(a, count);
+    assert_eq!(b, count * (count - 1) / 2);
+}
+
+#[test]
+fn abort() {
+    let stm = Stm::new();
+
+    let var = TVar::new(1);
+
+    let take = || {
+        stm.try_atomically_fn(|context| {
+            let value = context.read(&var)?;
+            if value == 0 {
+                return context.abort(Empty);
+            }
+            context.write(&var, value - 1)
+        })
+    };
+
+    assert_eq!(take(), Ok(()));
+    assert_eq!(take(), Err(Empty));
+}
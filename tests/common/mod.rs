@@ -0,0 +1,78 @@
+// Every test binary uses only a part of it
+#![allow(dead_code)]
+
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Barrier,
+};
+use xstm::{Algorithm, Stm, TVar};
+/// Run `f` with a `conflict` function, which runs `write` on another thread and waits for it
+///
+/// So a running transaction can conflict with another one without nesting `atomically`
+pub fn with_conflicts<R>(write: impl Fn() + Sync, f: impl FnOnce(&dyn Fn()) -> R) -> R {
+    let barrier = Barrier::new(2);
+    let done = AtomicBool::new(false);
+
+    std::thread::scope(|scope| {
+        scope.spawn(|| loop {
+            barrier.wait();
+            if done.load(Ordering::SeqCst) {
+                break;
+            }
+
+            write();
+            barrier.wait();
+        });
+
+        let conflict = || {
+            barrier.wait();
+            barrier.wait();
+        };
+        let result = f(&conflict);
+
+        done.store(true, Ordering::SeqCst);
+        barrier.wait();
+        result
+    })
+}
+
+/// Increment 10 `TVar`s together in some threads, check they are always the same in others
+pub fn counter<A: Algorithm + Sync>(stm: Stm<A>) {
+    let vars = std::iter::repeat_n(0, 10).map(TVar::new).collect::<Vec<_>>();
+
+    let thread_count = 4;
+    let repeat_count = 500;
+
+    std::thread::scope(|scope| {
+        for _ in 0..thread_count {
+            scope.spawn(|| {
+                for _ in 0..repeat_count {
+                    stm.atomically_fn(|context| {
+                        for var in &vars {
+                            let x = context.read(var)?;
+                            context.write(var, x + 1)?;
+                        }
+                        Ok(())
+                    });
+                }
+            });
+        }
+
+        // never a value written in place before committing, or an inconsistent old one
+        for _ in 0..thread_count {
+            scope.spawn(|| {
+                for _ in 0..repeat_count {
+                    let values = stm.atomically_fn(|context| {
+                        vars.iter()
+                            .map(|var| context.read(var))
+                            .collect::<Result<Vec<_>, _>>()
+                    });
+                    assert!(values.windows(2).all(|window| window[0] == window[1]));
+                }
+            });
+        }
+    });
+
+    let total = stm.atomically_fn(|context| context.read(&vars[0]));
+    assert_eq!(total, thread_count * repeat_count);
+}
@@ -0,0 +1,150 @@
+mod common;
+
+use common::with_conflicts;
+use std::{
+    cell::Cell,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Barrier,
+    },
+};
+use xstm::{Aggressive, Backoff, ContentionManager, Karma, Polka, Progress, Stm, TVar, Tl2, Yield};
+
+fn hot_counter<C: ContentionManager + Sync>(stm: Stm<Tl2, C>) {
+    let counter = TVar::new(0);
+
+    let thread_count = 4;
+    let repeat_count = 500;
+
+    std::thread::scope(|scope| {
+        for _ in 0..thread_count {
+            scope.spawn(|| {
+                for _ in 0..repeat_count {
+                    stm.atomically_fn(|context| {
+                        let x = context.read(&counter)?;
+                        context.write(&counter, x + 1)
+                    });
+                }
+            });
+        }
+    });
+
+    assert_eq!(stm.atomically(counter.read()), thread_count * repeat_count);
+}
+
+#[test]
+fn managers() {
+    hot_counter(Stm::new().contention_manager(Aggressive::new()));
+    hot_counter(Stm::new().contention_manager(Backoff::new()));
+    hot_counter(Stm::new().contention_manager(Backoff::new().randomized()));
+    hot_counter(Stm::new().contention_manager(Yield::new()));
+    hot_counter(Stm::new().contention_manager(Karma::new()));
+    hot_counter(Stm::new().contention_manager(Polka::new()));
+
+    let stm = Stm::with_algorithm(Tl2::new().eager());
+    hot_counter(stm.contention_manager(Polka::new()));
+}
+
+#[derive(Default)]
+struct Count {
+    aborts: AtomicUsize,
+}
+
+impl ContentionManager for Count {
+    fn on_busy_lock(&self, _: &Progress, _: usize, _: usize) -> bool {
+        false
+    }
+
+    fn on_abort(&self, progress: &Progress) {
+        let aborts = self.aborts.fetch_add(1, Ordering::SeqCst) + 1;
+        assert_eq!(progress.aborts(), aborts);
+    }
+}
+
+#[test]
+fn on_abort() {
+    let stm = Stm::new().contention_manager(Count::default());
+    let var = TVar::new(0);
+    let attempts = Cell::new(0);
+
+    with_conflicts(
+        || stm.atomically(var.write(10)),
+        |conflict| {
+            stm.atomically_fn(|context| {
+                attempts.set(attempts.get() + 1);
+
+                let x = context.read(&var)?;
+                if attempts.get() == 2 {
+                    // conflict with the transaction
+                    conflict();
+                }
+                context.write(&var, x + 1)
+            })
+        },
+    );
+
+    // The first attempt switched to write context, it's not a conflict
+    assert_eq!(attempts.get(), 3);
+    assert_eq!(stm.atomically(var.read()), 11);
+}
+
+// Record the priority of the lock holder
+struct Holder<'a> {
+    priority: &'a AtomicUsize,
+}
+
+impl ContentionManager for Holder<'_> {
+    fn on_busy_lock(&self, _: &Progress, holder: usize, _: usize) -> bool {
+        self.priority.store(holder, Ordering::SeqCst);
+        false
+    }
+
+    fn on_abort(&self, _: &Progress) {}
+}
+
+#[test]
+fn holder_priority() {
+    let priority = AtomicUsize::new(0);
+    // The eager writes keep the locks until commit
+    let stm = Stm::with_algorithm(Tl2::new().eager()).contention_manager(Holder {
+        priority: &priority,
+    });
+    let vars = std::iter::repeat_n(0, 5).map(TVar::new).collect::<Vec<_>>();
+    let x = TVar::new(0);
+    let barrier = Barrier::new(2);
+
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            let first = Cell::new(true);
+            stm.atomically_fn(|context| {
+                for var in &vars {
+                    context.write(var, 1)?;
+                }
+                context.write(&x, 1)?;
+
+                if first.replace(false) {
+                    // x is locked, let the other one try it until it gave up once
+                    barrier.wait();
+                    barrier.wait();
+                }
+                Ok(())
+            });
+        });
+
+        scope.spawn(|| {
+            barrier.wait();
+            stm.atomically_fn(|context| {
+                if priority.load(Ordering::SeqCst) != 0 {
+                    // Gave up, let the holder commit
+                    barrier.wait();
+                    return Ok(());
+                }
+                context.write(&x, 2)
+            });
+        });
+    });
+
+    // The write switching from the read-only context, then 6 writes
+    assert_eq!(priority.load(Ordering::SeqCst), 7);
+    assert_eq!(stm.atomically(x.read()), 1);
+}
@@ -0,0 +1,111 @@
+mod common;
+
+use common::with_conflicts;
+use std::{cell::Cell, sync::Barrier};
+use xstm::{
+    Algorithm, Context, ContentionManager, Failed, Progress, Stm, StmError, TVar, Tl2, Transaction,
+    TransactionExt,
+};
+
+struct WriteThenRetry<'a> {
+    var: &'a TVar<i32>,
+    value: i32,
+}
+
+impl<'a> Transaction for WriteThenRetry<'a> {
+    type Output = i32;
+
+    fn atomically<'var, A: Algorithm>(
+        &'var self,
+        context: &mut Context<'var, A>,
+    ) -> Result<Self::Output, StmError> {
+        context.write(self.var, self.value)?;
+        context.retry()
+    }
+}
+
+#[test]
+fn rollback() {
+    let stm = Stm::with_algorithm(Tl2::new().eager());
+    let var = TVar::new(1);
+
+    // user abort restores the value written in place and unlocks the TVar
+    let result = stm.try_atomically_fn(|context| {
+        context.write(&var, 2)?;
+        context.write(&var, 3)?;
+        context.abort("aborted")
+    });
+    assert_eq!(result, Err::<(), _>("aborted"));
+    assert_eq!(stm.atomically(var.read()), 1);
+
+    // or_else rolls back the value written in place by the first branch
+    let value = stm.atomically(
+        WriteThenRetry {
+            var: &var,
+            value: 2,
+        }
+        .or_else(var.read()),
+    );
+    assert_eq!(value, 1);
+    assert_eq!(stm.atomically(var.read()), 1);
+}
+
+// Let another thread check the TVars while the next attempt is delayed
+struct Pause<'a> {
+    barrier: &'a Barrier,
+}
+
+impl ContentionManager for Pause<'_> {
+    fn on_busy_lock(&self, _: &Progress, _: usize, _: usize) -> bool {
+        false
+    }
+
+    fn on_abort(&self, _: &Progress) {
+        self.barrier.wait();
+        self.barrier.wait();
+    }
+}
+
+#[test]
+fn unlocked_on_abort() {
+    let x = TVar::new(0);
+    let y = TVar::new(0);
+    let barrier = Barrier::new(2);
+    let stm = Stm::with_algorithm(Tl2::new().eager()).contention_manager(Pause {
+        barrier: &barrier,
+    });
+    let attempts = Cell::new(0);
+
+    std::thread::scope(|scope| {
+        // Only the second attempt aborts for a conflict
+        scope.spawn(|| {
+            barrier.wait();
+            let read: Result<i32, Failed> =
+                stm.try_atomically_fn_with(|context| context.read(&x), 1);
+            barrier.wait();
+            assert_eq!(read, Ok(0));
+        });
+
+        with_conflicts(
+            || stm.atomically(y.write(1)),
+            |conflict| {
+                stm.atomically_fn(|context| {
+                    attempts.set(attempts.get() + 1);
+
+                    let value = context.read(&y)?;
+                    // locked until the attempt ends
+                    context.write(&x, value + 1)?;
+                    if attempts.get() == 2 {
+                        // the commit fails to validate y
+                        conflict();
+                    }
+                    Ok(())
+                })
+            },
+        );
+    });
+
+    // The first attempt switched to write context
+    assert_eq!(attempts.get(), 3);
+    assert_eq!(stm.atomically(x.read()), 2);
+}
@@ -0,0 +1,61 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use xstm::{Stm, TBox};
+
+// Count the values alive
+struct Item(Arc<AtomicUsize>);
+
+impl Item {
+    fn new(live: &Arc<AtomicUsize>) -> Self {
+        live.fetch_add(1, Ordering::SeqCst);
+        Item(live.clone())
+    }
+}
+
+impl Clone for Item {
+    fn clone(&self) -> Self {
+        Item::new(&self.0)
+    }
+}
+
+impl Drop for Item {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// Alone in this binary, no other test keeps a thread pinned
+#[test]
+fn freed_on_exit() {
+    let stm = Stm::new();
+    let live = Arc::new(AtomicUsize::new(0));
+    let items = TBox::new(Vec::<Item>::new());
+
+    std::thread::scope(|scope| {
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                scope.spawn(|| {
+                    for _ in 0..50 {
+                        stm.atomically_fn(|context| {
+                            let mut list = context.read_box(&items)?;
+                            list.push(Item::new(&live));
+                            context.write_box(&items, list)
+                        });
+                    }
+                })
+            })
+            .collect();
+
+        // Unlike the scope, join waits for the thread-locals to be dropped
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    });
+
+    // Every replaced list was freed without any later write
+    assert_eq!(live.load(Ordering::SeqCst), 200);
+    drop(items);
+    assert_eq!(live.load(Ordering::SeqCst), 0);
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use common::with_conflicts;
+use std::cell::Cell;
+use xstm::{Algorithm, Stm, TVar, Tl2};
+
+/// Another transaction commits to `b` after `a` was read in every attempt,
+/// it can only finish by extending the snapshot
+fn read_after_commit<A: Algorithm + Sync>(stm: &Stm<A>) {
+    let a = TVar::new(1);
+    let b = TVar::new(1);
+    let attempts = Cell::new(0);
+
+    let increment = || {
+        stm.atomically_fn(|context| {
+            let y = context.read(&b)?;
+            context.write(&b, y + 1)
+        })
+    };
+
+    let sum = with_conflicts(increment, |conflict| {
+        stm.atomically_fn(|context| {
+            attempts.set(attempts.get() + 1);
+
+            let x = context.read(&a)?;
+            conflict();
+            let y = context.read(&b)?;
+
+            Ok(x + y)
+        })
+    });
+
+    // The read-only attempt cannot be extended
+    assert_eq!(attempts.get(), 2);
+    assert_eq!(sum, 4);
+}
+
+#[test]
+fn lazy() {
+    read_after_commit(&Stm::new());
+}
+
+#[test]
+fn eager() {
+    read_after_commit(&Stm::with_algorithm(Tl2::new().eager()));
+}
+
+#[test]
+fn changed() {
+    let stm = Stm::new();
+    let a = TVar::new(1);
+    let attempts = Cell::new(0);
+
+    let increment = || {
+        stm.atomically_fn(|context| {
+            let x = context.read(&a)?;
+            context.write(&a, x + 1)
+        })
+    };
+
+    // `a` was changed after reading, the snapshot cannot be extended
+    with_conflicts(increment, |conflict| {
+        stm.atomically_fn(|context| {
+            attempts.set(attempts.get() + 1);
+
+            context.read(&a)?;
+            if attempts.get() <= 2 {
+                conflict();
+            }
+            context.read(&a)
+        })
+    });
+
+    assert_eq!(attempts.get(), 3);
+}
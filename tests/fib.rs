@@ -1,5 +1,5 @@
 use std::sync::Arc;
-use xstm::{Context, Stm, StmError, TVar, Transaction};
+use xstm::{Algorithm, Context, Stm, StmError, TVar, Transaction};
 
 struct Fib {
     var_index: Arc<TVar<i32>>,
@@ -10,16 +10,16 @@ struct Fib {
 impl Transaction for Fib {
     type Output = (i32, u128);
 
-    fn atomically<'this: 'var, 'context, 'var>(
-        &'this self,
-        context: &'context mut Context<'var>,
+    fn atomically<'var, A: Algorithm>(
+        &'var self,
+        context: &mut Context<'var, A>,
     ) -> Result<Self::Output, StmError> {
         let index = context.read(&self.var_index)?;
         context.write(&self.var_index, index + 1)?;
 
         let a = context.read(&self.var_a)?;
         let b = context.read(&self.var_b)?;
-        let result = a + b;
+        let result = a.wrapping_add(b);
 
         context.write(&self.var_a, b)?;
         context.write(&self.var_b, result)?;
@@ -110,12 +110,12 @@ async fn basic() {
 }
 
 fn fib(count: i32) -> Vec<u128> {
-    let mut a = 1;
+    let mut a: u128 = 1;
     let mut b = 1;
 
     let mut v = Vec::new();
     for _ in 0..count {
-        let result = a + b;
+        let result = a.wrapping_add(b);
 
         a = b;
         b = result;
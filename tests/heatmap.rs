@@ -0,0 +1,84 @@
+mod common;
+
+use common::with_conflicts;
+use xstm::{Algorithm, Context, Failed, Stm, StmError, TVar, Transaction};
+
+struct Deposit<'a> {
+    conflict: &'a dyn Fn(),
+    balance: &'a TVar<i32>,
+    log: &'a TVar<i32>,
+}
+
+impl<'a> Transaction for Deposit<'a> {
+    type Output = ();
+
+    fn atomically<'var, A: Algorithm>(
+        &'var self,
+        context: &mut Context<'var, A>,
+    ) -> Result<Self::Output, StmError> {
+        let x = context.read(self.balance)?;
+        // conflict with the transaction on balance
+        (self.conflict)();
+        context.write(self.log, x)
+    }
+}
+
+#[test]
+fn report() {
+    let stm = Stm::new().track_conflicts();
+    let balance = TVar::named("balance", 0);
+    let limit = TVar::named("limit", 0);
+    let log = TVar::named("log", 0);
+
+    let increment = |var: &TVar<i32>| {
+        stm.atomically_fn(|context| {
+            let x = context.read(var)?;
+            context.write(var, x + 1)
+        })
+    };
+
+    let result = with_conflicts(
+        || increment(&balance),
+        |conflict| {
+            let deposit = Deposit {
+                conflict,
+                balance: &balance,
+                log: &log,
+            };
+            stm.try_atomically_with(deposit, 4)
+        },
+    );
+    assert!(result.is_err());
+
+    let result: Result<(), Failed> = with_conflicts(
+        || increment(&limit),
+        |conflict| {
+            stm.try_atomically_fn_with(
+                |context| {
+                    let x = context.read(&limit)?;
+                    conflict();
+                    context.write(&log, x)
+                },
+                3,
+            )
+        },
+    );
+    assert!(result.is_err());
+
+    let report = stm.conflict_report().unwrap();
+    let top = report.top(10);
+
+    // The first attempts only switched to write context
+    assert_eq!(top.len(), 2);
+    assert_eq!(top[0].var().name(), Some("balance"));
+    assert_eq!(top[0].aborts(), 3);
+    assert_eq!(top[1].var().name(), Some("limit"));
+    assert_eq!(top[1].aborts(), 2);
+
+    let (kind, aborts) = top[0].transactions()[0];
+    assert!(kind.starts_with("heatmap::Deposit"));
+    assert_eq!(aborts, 3);
+
+    assert!(report.to_string().starts_with("balance: 3 aborts\n"));
+    assert!(Stm::new().conflict_report().is_none());
+}
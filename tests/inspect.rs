@@ -0,0 +1,95 @@
+use std::{cell::Cell, sync::Barrier};
+use xstm::{Algorithm, Context, NOrec, Stm, StmError, TVar, Tl2};
+
+fn min_max<'var, A: Algorithm>(
+    context: &mut Context<'var, A>,
+    array: &'var TVar<[u64; 1024]>,
+) -> Result<(Option<u64>, Option<u64>), StmError> {
+    context.inspect(array, |a| (a.iter().min().copied(), a.iter().max().copied()))
+}
+
+fn consistent<A: Algorithm + Sync>(stm: Stm<A>) {
+    let array = TVar::new([0u64; 1024]);
+
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            for _ in 0..100 {
+                stm.atomically_fn(|context| {
+                    let mut value = context.read(&array)?;
+                    value.iter_mut().for_each(|x| *x += 1);
+                    context.write(&array, value)
+                });
+            }
+        });
+
+        for irrevocable in [false, true] {
+            let (stm, array) = (&stm, &array);
+            scope.spawn(move || {
+                for _ in 0..100 {
+                    // f never sees a value being written
+                    let (min, max) = if irrevocable {
+                        // in place, the writers wait
+                        stm.irrevocably_fn(|context| min_max(context, array))
+                    } else {
+                        stm.atomically_fn(|context| min_max(context, array))
+                    };
+                    assert_eq!(min, max);
+                }
+            });
+        }
+    });
+
+    let sum = stm.atomically_fn(|context| context.inspect(&array, |a| a.iter().sum::<u64>()));
+    assert_eq!(sum, 100 * 1024);
+}
+
+#[test]
+fn inspect() {
+    consistent(Stm::new());
+    consistent(Stm::with_algorithm(Tl2::new().eager()));
+    consistent(Stm::with_algorithm(NOrec::new()));
+}
+
+#[test]
+fn own_write() {
+    let stm = Stm::new();
+    let var = TVar::new([1, 2, 3]);
+
+    let last = stm.atomically_fn(|context| {
+        context.write(&var, [4, 5, 6])?;
+        context.inspect(&var, |a| a[2])
+    });
+
+    assert_eq!(last, 6);
+}
+
+#[test]
+fn irrevocable_retry() {
+    let stm = Stm::with_algorithm(NOrec::new());
+    let var = TVar::new(0);
+    let barrier = Barrier::new(2);
+    let attempts = Cell::new(0);
+
+    std::thread::scope(|scope| {
+        // Blocked until the irrevocable attempt releases the sequence
+        scope.spawn(|| {
+            barrier.wait();
+            stm.atomically(var.write(1));
+        });
+
+        // Read in place, then waits for the change
+        let value = stm.irrevocably_fn(|context| {
+            attempts.set(attempts.get() + 1);
+
+            let value = context.inspect(&var, |x| *x)?;
+            if value == 0 {
+                if attempts.get() == 1 {
+                    barrier.wait();
+                }
+                return context.retry();
+            }
+            Ok(value)
+        });
+        assert_eq!(value, 1);
+    });
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use common::with_conflicts;
+use std::{
+    cell::Cell,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use xstm::{Algorithm, ClockStrategy, NOrec, Stm, TVar, Tl2};
+
+fn run_once<A: Algorithm + Sync>(stm: Stm<A>) {
+    let counter = TVar::new(0);
+    let total = TVar::new(0);
+    let runs = AtomicUsize::new(0);
+
+    let thread_count = 4;
+    let repeat_count = 200;
+
+    std::thread::scope(|scope| {
+        for _ in 0..thread_count {
+            scope.spawn(|| {
+                for _ in 0..repeat_count {
+                    stm.atomically_fn(|context| {
+                        let x = context.read(&counter)?;
+                        context.write(&counter, x + 1)
+                    });
+                }
+            });
+        }
+
+        scope.spawn(|| {
+            for _ in 0..repeat_count {
+                stm.irrevocably_fn(|context| {
+                    // never run again
+                    runs.fetch_add(1, Ordering::SeqCst);
+
+                    let x = context.read(&counter)?;
+                    let y = context.read(&total)?;
+                    context.write(&counter, x + 1)?;
+                    context.write(&total, y + 1)
+                });
+            }
+        });
+    });
+
+    assert_eq!(runs.load(Ordering::SeqCst), repeat_count);
+    assert_eq!(stm.atomically(total.read()), repeat_count);
+    assert_eq!(
+        stm.atomically(counter.read()),
+        (thread_count + 1) * repeat_count
+    );
+}
+
+#[test]
+fn irrevocably() {
+    run_once(Stm::new());
+    run_once(Stm::with_algorithm(Tl2::new().eager()));
+    run_once(Stm::with_algorithm(NOrec::new()));
+    run_once(Stm::with_algorithm(Tl2::new().clock(ClockStrategy::PerThread)));
+}
+
+#[test]
+fn escalation() {
+    let stm = Stm::new().irrevocable_after(3);
+    let var = TVar::new(0);
+    let attempts = Cell::new(0);
+
+    let add = || {
+        stm.atomically_fn(|context| {
+            let x = context.read(&var)?;
+            context.write(&var, x + 10)
+        })
+    };
+
+    with_conflicts(add, |conflict| {
+        stm.atomically_fn(|context| {
+            attempts.set(attempts.get() + 1);
+
+            let x = context.read(&var)?;
+            if attempts.get() <= 4 {
+                // conflict with the transaction until it's irrevocable
+                conflict();
+            }
+            context.write(&var, x + 1)
+        })
+    });
+
+    // The first attempt switched to write context, then 3 conflicts
+    assert_eq!(attempts.get(), 5);
+    assert_eq!(stm.atomically(var.read()), 41);
+}
@@ -0,0 +1,10 @@
+use std::mem::size_of;
+use xstm::TVar;
+
+#[test]
+fn no_metadata() {
+    // The value, the versioned lock and a pointer to the lazily allocated rest,
+    // the same for every algorithm, NOrec doesn't use the lock but still stores it
+    assert_eq!(size_of::<TVar<u64>>(), 3 * size_of::<usize>());
+    assert_eq!(size_of::<TVar<u8>>(), 3 * size_of::<usize>());
+}
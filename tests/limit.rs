@@ -0,0 +1,99 @@
+mod common;
+
+use common::with_conflicts;
+use std::{
+    cell::Cell,
+    time::{Duration, Instant},
+};
+use xstm::{AbortReason, Algorithm, Failed, NOrec, Stm, TVar};
+
+#[test]
+fn attempts() {
+    let stm = Stm::new();
+    let var = TVar::new(0);
+    let attempts = Cell::new(0);
+
+    let add = || {
+        stm.atomically_fn(|context| {
+            let x = context.read(&var)?;
+            context.write(&var, x + 10)
+        })
+    };
+
+    let result: Result<(), Failed> = with_conflicts(add, |conflict| {
+        stm.try_atomically_fn_with(
+            |context| {
+                attempts.set(attempts.get() + 1);
+
+                let x = context.read(&var)?;
+                // conflict with the transaction every time
+                conflict();
+                context.write(&var, x + 1)
+            },
+            3,
+        )
+    });
+
+    match result {
+        Err(Failed::GaveUp { attempts, reason }) => {
+            assert_eq!(attempts, 3);
+            assert_eq!(
+                reason,
+                AbortReason::CommitValidation {
+                    var: Some(var.id())
+                }
+            );
+        }
+        _ => panic!("committed"),
+    }
+
+    assert_eq!(attempts.get(), 3);
+    // Only the conflicting writes were committed
+    assert_eq!(stm.atomically(var.read()), 30);
+}
+
+fn expire<A: Algorithm>(stm: Stm<A>) {
+    let var = TVar::new(0);
+    let late = Cell::new(0);
+
+    let start = Instant::now();
+    let deadline = start + Duration::from_millis(20);
+
+    let result: Result<i32, Failed> = stm.try_atomically_fn_with(
+        |context| {
+            if Instant::now() >= deadline {
+                late.set(late.get() + 1);
+            }
+
+            let x = context.read(&var)?;
+            if x == 0 {
+                // nobody will change it
+                return context.retry();
+            }
+            Ok(x)
+        },
+        deadline,
+    );
+
+    assert!(matches!(
+        result,
+        Err(Failed::GaveUp {
+            reason: AbortReason::UserRetry,
+            ..
+        })
+    ));
+    assert!(start.elapsed() >= Duration::from_millis(20));
+    assert!(start.elapsed() < Duration::from_secs(5));
+    // No attempt started after the deadline
+    assert_eq!(late.get(), 0);
+
+    // Commits within the limit
+    stm.atomically(var.write(1));
+    assert_eq!(stm.try_atomically_with(var.read(), deadline), Ok(1));
+}
+
+#[test]
+fn deadline() {
+    expire(Stm::new());
+    expire(Stm::with_algorithm(NOrec::new()));
+}
@@ -0,0 +1,50 @@
+mod common;
+
+use common::with_conflicts;
+use std::cell::Cell;
+use xstm::{Stm, TVar, Tl2};
+
+/// Write `a` and `b` `writes` times after `a` was read in the first attempt
+fn read_old_values(stm: &Stm, writes: usize) -> (usize, (i32, i32)) {
+    let a = TVar::new(0);
+    let b = TVar::new(0);
+    let attempts = Cell::new(0);
+
+    let increment = || {
+        stm.atomically_fn(|context| {
+            let x = context.read(&a)?;
+            context.write(&a, x + 1)?;
+            let y = context.read(&b)?;
+            context.write(&b, y + 1)
+        })
+    };
+
+    let values = with_conflicts(increment, |conflict| {
+        stm.atomically_fn(|context| {
+            attempts.set(attempts.get() + 1);
+
+            let x = context.read(&a)?;
+            if attempts.get() == 1 {
+                for _ in 0..writes {
+                    conflict();
+                }
+            }
+            let y = context.read(&b)?;
+
+            Ok((x, y))
+        })
+    });
+
+    (attempts.get(), values)
+}
+
+#[test]
+fn history() {
+    let stm = Stm::with_algorithm(Tl2::new().multi_version(4));
+
+    // The snapshot is kept
+    assert_eq!(read_old_values(&stm, 4), (1, (0, 0)));
+
+    // The value at snapshot was dropped
+    assert_eq!(read_old_values(&stm, 5), (2, (5, 5)));
+}
@@ -0,0 +1,51 @@
+mod common;
+
+use common::with_conflicts;
+use xstm::{AbortReason, Failed, Stm, TVar, Tl2};
+
+fn conflicting_var(stm: &Stm) -> Option<&'static str> {
+    let balance = TVar::named("balance", 100);
+    let limit = TVar::named("limit", 50);
+
+    let deposit = || {
+        stm.atomically_fn(|context| {
+            let y = context.read(&balance)?;
+            context.write(&balance, y + 1)
+        })
+    };
+
+    let result: Result<(), Failed> = with_conflicts(deposit, |conflict| {
+        stm.try_atomically_fn_with(
+            |context| {
+                let x = context.read(&limit)?;
+                let y = context.read(&balance)?;
+                // conflict with the transaction on balance
+                conflict();
+                context.write(&limit, x + y)
+            },
+            2,
+        )
+    });
+
+    match result {
+        Err(Failed::GaveUp {
+            reason:
+                AbortReason::CommitValidation { var } | AbortReason::LockContention { var },
+            ..
+        }) => var.and_then(|var| var.name()),
+        _ => panic!("unexpected result {:?}", result),
+    }
+}
+
+#[test]
+fn attribution() {
+    assert_eq!(conflicting_var(&Stm::new()), Some("balance"));
+    assert_eq!(
+        conflicting_var(&Stm::with_algorithm(Tl2::new().eager())),
+        Some("balance")
+    );
+
+    let var = TVar::new(0);
+    assert_eq!(var.name(), None);
+    assert!(var.id().to_string().starts_with("TVar@0x"));
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use common::counter;
+use std::{sync::Arc, time::Duration};
+use xstm::{NOrec, Stm, TVar};
+
+#[test]
+fn basic() {
+    counter(Stm::with_algorithm(NOrec::new()));
+}
+
+#[test]
+fn retry() {
+    let stm = Arc::new(Stm::with_algorithm(NOrec::new()));
+    let flag = Arc::new(TVar::new(false));
+
+    let _stm = stm.clone();
+    let _flag = flag.clone();
+    let handle = std::thread::spawn(move || {
+        _stm.atomically_fn(|context| {
+            if !context.read(&_flag)? {
+                return context.retry();
+            }
+            context.write(&_flag, false)
+        })
+    });
+
+    // let the transaction block
+    std::thread::sleep(Duration::from_millis(100));
+
+    stm.atomically(flag.write(true));
+
+    handle.join().unwrap();
+    assert!(!stm.atomically(flag.read()));
+}
@@ -0,0 +1,113 @@
+mod common;
+
+use common::with_conflicts;
+use std::{
+    cell::Cell,
+    sync::Mutex,
+    thread::{self, ThreadId},
+};
+use xstm::{AbortReason, CommitInfo, Stm, StmObserver, TVar};
+
+#[derive(Debug, PartialEq)]
+enum Event {
+    Start(usize),
+    Abort(AbortReason),
+    Commit(CommitInfo),
+    Escalation(usize),
+}
+
+#[derive(Default)]
+struct Record {
+    events: Mutex<Vec<(ThreadId, Event)>>,
+}
+
+impl Record {
+    fn push(&self, event: Event) {
+        let id = thread::current().id();
+        self.events.lock().unwrap().push((id, event))
+    }
+
+    /// The events of the current thread
+    fn take(&self) -> Vec<Event> {
+        let id = thread::current().id();
+        let events = std::mem::take(&mut *self.events.lock().unwrap());
+
+        events
+            .into_iter()
+            .filter(|(thread, _)| *thread == id)
+            .map(|(_, event)| event)
+            .collect()
+    }
+}
+
+impl StmObserver for Record {
+    fn on_attempt_start(&self, attempt: usize) {
+        self.push(Event::Start(attempt))
+    }
+
+    fn on_abort(&self, reason: AbortReason) {
+        self.push(Event::Abort(reason))
+    }
+
+    fn on_commit(&self, commit: &CommitInfo) {
+        self.push(Event::Commit(*commit))
+    }
+
+    fn on_escalation(&self, aborts: usize) {
+        self.push(Event::Escalation(aborts))
+    }
+}
+
+#[test]
+fn events() {
+    let record = Record::default();
+    let stm = Stm::new().irrevocable_after(1).observer(&record);
+    let var = TVar::new(0);
+    let other = TVar::new(0);
+
+    stm.atomically(var.read());
+    assert_eq!(
+        record.take(),
+        vec![Event::Start(1), Event::Commit(CommitInfo::new(1, 0, None))]
+    );
+
+    let attempts = Cell::new(0);
+    with_conflicts(
+        || stm.atomically(var.write(1)),
+        |conflict| {
+            stm.atomically_fn(|context| {
+                attempts.set(attempts.get() + 1);
+
+                let x = context.read(&var)?;
+                if attempts.get() == 2 {
+                    // conflict with the transaction once
+                    conflict();
+                }
+                let y = context.read(&other)?;
+                context.write(&other, x + y)
+            })
+        },
+    );
+
+    let events = record.take();
+    assert_eq!(
+        events[..5],
+        [
+            Event::Start(1),
+            Event::Abort(AbortReason::WriteInReadOnly),
+            Event::Start(2),
+            Event::Abort(AbortReason::CommitValidation {
+                var: Some(var.id())
+            }),
+            Event::Escalation(1),
+        ]
+    );
+
+    match events[5..] {
+        [Event::Start(3), Event::Commit(commit)] => {
+            assert_eq!((commit.reads(), commit.writes()), (2, 1));
+            assert!(commit.write_version().is_some());
+        }
+        _ => panic!("unexpected events {:?}", events),
+    }
+}
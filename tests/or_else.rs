@@ -0,0 +1,87 @@
+use std::{sync::Arc, time::Duration};
+use xstm::{Algorithm, Context, Stm, StmError, TVar, Transaction, TransactionExt};
+
+/// Count the attempt in `log`, then take an item from `queue`
+struct Take {
+    queue: Arc<TVar<usize>>,
+    log: Arc<TVar<usize>>,
+}
+
+impl Transaction for Take {
+    type Output = usize;
+
+    fn atomically<'var, A: Algorithm>(
+        &'var self,
+        context: &mut Context<'var, A>,
+    ) -> Result<Self::Output, StmError> {
+        let log = context.read(&self.log)?;
+        context.write(&self.log, log + 1)?;
+
+        let items = context.read(&self.queue)?;
+
+        if items == 0 {
+            return context.retry();
+        }
+
+        context.write(&self.queue, items - 1)?;
+
+        Ok(items)
+    }
+}
+
+#[test]
+fn rollback_first() {
+    let stm = Stm::new();
+
+    let queue_a = Arc::new(TVar::new(0));
+    let queue_b = Arc::new(TVar::new(2));
+    let log = Arc::new(TVar::new(0));
+
+    let take_a = Take {
+        queue: queue_a.clone(),
+        log: log.clone(),
+    };
+    let take_b = Take {
+        queue: queue_b.clone(),
+        log: log.clone(),
+    };
+
+    let taken = stm.atomically(take_a.or_else(take_b));
+
+    assert_eq!(taken, 2);
+    assert_eq!(stm.atomically(queue_a.read()), 0);
+    assert_eq!(stm.atomically(queue_b.read()), 1);
+    // the write of take_a was rolled back
+    assert_eq!(stm.atomically(log.read()), 1);
+}
+
+#[tokio::test]
+async fn wait_both() {
+    let stm = Arc::new(Stm::new());
+
+    let queue_a = Arc::new(TVar::new(0));
+    let queue_b = Arc::new(TVar::new(0));
+    let log = Arc::new(TVar::new(0));
+
+    let take_a = Take {
+        queue: queue_a.clone(),
+        log: log.clone(),
+    };
+    let take_b = Take {
+        queue: queue_b.clone(),
+        log: log.clone(),
+    };
+
+    let _stm = stm.clone();
+    let handle = tokio::task::spawn_blocking(move || _stm.atomically(take_a.or_else(take_b)));
+
+    // let the transaction block
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    // wake it up by the second read set
+    stm.atomically(queue_b.write(1));
+
+    assert_eq!(handle.await.unwrap(), 1);
+    assert_eq!(stm.atomically(queue_b.read()), 0);
+    assert_eq!(stm.atomically(log.read()), 1);
+}
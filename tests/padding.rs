@@ -0,0 +1,58 @@
+use xstm::{Algorithm, NOrec, Stm, TVar, Tl2};
+
+// 7 padding bytes after the u8
+type Padded = (u8, u64);
+
+fn increment<A: Algorithm + Sync>(stm: Stm<A>) {
+    // Moved after the padding was frozen
+    let var = Box::new(TVar::new((0u8, 0u64)));
+
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..100 {
+                    stm.atomically_fn(|context| {
+                        let (_, count): Padded = context.read(&var)?;
+                        context.write(&var, ((count + 1) as u8, count + 1))
+                    });
+                }
+            });
+        }
+    });
+
+    assert_eq!(stm.atomically(var.read()), (144, 400));
+}
+
+#[test]
+#[cfg_attr(miri, ignore = "the padding is frozen by inline asm, which Miri can't run")]
+fn padded() {
+    increment(Stm::new());
+    increment(Stm::with_algorithm(Tl2::new().eager()));
+    increment(Stm::with_algorithm(NOrec::new()));
+}
+
+// The entries after a u8 in the write set are aligned to their types
+fn mixed<A: Algorithm>(stm: Stm<A>) {
+    let (a, b, c) = (TVar::new(0u8), TVar::new(0u64), TVar::new(0u128));
+
+    stm.atomically_fn(|context| {
+        context.write(&a, 1)?;
+        context.write(&b, 2)?;
+        context.write(&c, 3)?;
+
+        assert_eq!(context.read(&b)?, 2);
+        assert_eq!(context.read(&c)?, 3);
+        Ok(())
+    });
+
+    let values = stm.atomically_fn(|context| {
+        Ok((context.read(&a)?, context.read(&b)?, context.read(&c)?))
+    });
+    assert_eq!(values, (1, 2, 3));
+}
+
+#[test]
+fn aligned() {
+    mixed(Stm::new());
+    mixed(Stm::with_algorithm(NOrec::new()));
+}
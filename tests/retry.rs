@@ -0,0 +1,111 @@
+use std::{
+    cell::Cell,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use xstm::{AbortReason, Algorithm, Context, Failed, NOrec, Stm, StmError, TVar, Transaction};
+
+struct Take {
+    items: Arc<TVar<usize>>,
+}
+
+impl Transaction for Take {
+    type Output = usize;
+
+    fn atomically<'var, A: Algorithm>(
+        &'var self,
+        context: &mut Context<'var, A>,
+    ) -> Result<Self::Output, StmError> {
+        let items = context.read(&self.items)?;
+
+        if items == 0 {
+            // block until some items were put
+            return context.retry();
+        }
+
+        context.write(&self.items, items - 1)?;
+
+        Ok(items)
+    }
+}
+
+struct Put {
+    items: Arc<TVar<usize>>,
+}
+
+impl Transaction for Put {
+    type Output = ();
+
+    fn atomically<'var, A: Algorithm>(
+        &'var self,
+        context: &mut Context<'var, A>,
+    ) -> Result<Self::Output, StmError> {
+        let items = context.read(&self.items)?;
+        context.write(&self.items, items + 1)
+    }
+}
+
+#[tokio::test]
+async fn basic() {
+    let stm = Arc::new(Stm::new());
+    let items = Arc::new(TVar::new(0));
+
+    let consumer_count = 8;
+
+    let mut handles = Vec::new();
+    for _ in 0..consumer_count {
+        let _stm = stm.clone();
+        let take = Take {
+            items: items.clone(),
+        };
+        let handle = tokio::task::spawn_blocking(move || _stm.atomically(take));
+        handles.push(handle);
+    }
+
+    // let the consumers block
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    for _ in 0..consumer_count {
+        stm.atomically(Put {
+            items: items.clone(),
+        });
+    }
+
+    for handle in handles {
+        let taken = handle.await.unwrap();
+        assert!(taken > 0);
+    }
+
+    assert_eq!(stm.atomically(items.read()), 0);
+}
+
+fn nothing_read<A: Algorithm>(stm: Stm<A>) {
+    let attempts = Cell::new(0);
+    let start = Instant::now();
+
+    let result: Result<(), Failed> = stm.try_atomically_fn_with(
+        |context| {
+            attempts.set(attempts.get() + 1);
+            context.retry()
+        },
+        start + Duration::from_millis(50),
+    );
+
+    assert!(matches!(
+        result,
+        Err(Failed::GaveUp {
+            reason: AbortReason::UserRetry,
+            ..
+        })
+    ));
+    assert!(start.elapsed() >= Duration::from_millis(50));
+    // Blocked until the deadline instead of running again and again,
+    // Tl2 runs it once more in write context
+    assert!(attempts.get() <= 2);
+}
+
+#[test]
+fn empty_read_set() {
+    nothing_read(Stm::new());
+    nothing_read(Stm::with_algorithm(NOrec::new()));
+}
@@ -0,0 +1,62 @@
+use xstm::{Algorithm, NOrec, SafeRead, Stm, TVar, Tl2};
+
+// A fixed-capacity string, Clone but not Copy
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Text {
+    len: usize,
+    bytes: [u8; 64],
+}
+
+unsafe impl SafeRead for Text {}
+
+impl Text {
+    fn new() -> Self {
+        Text {
+            len: 0,
+            bytes: [0; 64],
+        }
+    }
+
+    fn push(&mut self, byte: u8) {
+        self.bytes[self.len] = byte;
+        self.len += 1;
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+fn append<A: Algorithm + Sync>(stm: Stm<A>) {
+    let text = TVar::new(Text::new());
+
+    std::thread::scope(|scope| {
+        for byte in [b'a', b'b', b'c', b'd'] {
+            let stm = &stm;
+            let text = &text;
+            scope.spawn(move || {
+                for _ in 0..16 {
+                    stm.atomically_fn(|context| {
+                        let mut value = context.read(text)?;
+                        value.push(byte);
+                        context.write(text, value)
+                    });
+                }
+            });
+        }
+    });
+
+    let text = stm.atomically(text.read());
+    assert_eq!(text.len, 64);
+    for byte in [b'a', b'b', b'c', b'd'] {
+        let count = text.as_bytes().iter().filter(|&&b| b == byte).count();
+        assert_eq!(count, 16);
+    }
+}
+
+#[test]
+fn clone_not_copy() {
+    append(Stm::new());
+    append(Stm::with_algorithm(Tl2::new().eager()));
+    append(Stm::with_algorithm(NOrec::new()));
+}
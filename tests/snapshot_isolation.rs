@@ -0,0 +1,61 @@
+mod common;
+
+use common::with_conflicts;
+use std::cell::Cell;
+use xstm::{Algorithm, Stm, TVar, Tl2};
+
+/// Another transaction writes `a` or `b` after they were read,
+/// return the attempts of the transaction writing `a`
+fn attempts<A: Algorithm + Sync>(stm: &Stm<A>, write_a: bool) -> usize {
+    let a = TVar::new(1);
+    let b = TVar::new(1);
+    let c = TVar::new(0);
+    let attempts = Cell::new(0);
+    let done = Cell::new(false);
+
+    let other = if write_a { &a } else { &b };
+    with_conflicts(
+        || stm.atomically(other.write(0)),
+        |conflict| {
+            stm.atomically_fn(|context| {
+                attempts.set(attempts.get() + 1);
+
+                // run in write context from the second attempt
+                context.write(&c, 1)?;
+
+                let x = context.read(&a)?;
+                let y = context.read(&b)?;
+                if !done.get() {
+                    conflict();
+                    done.set(true);
+                }
+                context.write(&a, x + y)
+            })
+        },
+    );
+
+    attempts.get()
+}
+
+#[test]
+fn write_skew() {
+    // b was read and changed by other transaction
+    assert_eq!(attempts(&Stm::new(), false), 3);
+
+    // b was only read, the transaction commits in snapshot isolation
+    let stm = Stm::with_algorithm(Tl2::new().snapshot_isolation());
+    assert_eq!(attempts(&stm, false), 2);
+
+    let stm = Stm::with_algorithm(Tl2::new().eager().snapshot_isolation());
+    assert_eq!(attempts(&stm, false), 2);
+}
+
+#[test]
+fn write_write() {
+    // a was changed by other transaction, it's a conflict anyway
+    let stm = Stm::with_algorithm(Tl2::new().snapshot_isolation());
+    assert_eq!(attempts(&stm, true), 3);
+
+    let stm = Stm::with_algorithm(Tl2::new().eager().snapshot_isolation());
+    assert_eq!(attempts(&stm, true), 3);
+}
@@ -0,0 +1,71 @@
+use std::cell::Cell;
+use xstm::{Stm, TVar};
+
+#[test]
+fn counts() {
+    let stm = Stm::new();
+    let counter = TVar::new(0);
+
+    let thread_count = 4;
+    let repeat_count = 500;
+
+    std::thread::scope(|scope| {
+        for _ in 0..thread_count {
+            scope.spawn(|| {
+                for _ in 0..repeat_count {
+                    stm.atomically_fn(|context| {
+                        let x = context.read(&counter)?;
+                        context.write(&counter, x + 1)
+                    });
+                    stm.atomically(counter.read());
+                }
+            });
+        }
+    });
+
+    let stats = stm.stats();
+    let transactions = (thread_count * repeat_count) as u64;
+
+    assert_eq!(stats.write_commits(), transactions);
+    assert_eq!(stats.read_only_commits(), transactions);
+    assert_eq!(stats.commits(), 2 * transactions);
+    assert_eq!(stats.average_reads(), 1.0);
+    assert_eq!(stats.average_writes(), 1.0);
+
+    // A writing transaction switches to write context at most once
+    assert!(stats.promotions() <= transactions);
+    assert_eq!(
+        stats.aborts(),
+        stats.read_validation_aborts()
+            + stats.lock_failures()
+            + stats.commit_validation_aborts()
+            + stats.irrevocable_aborts()
+    );
+    assert_eq!(stats.user_retries(), 0);
+}
+
+#[test]
+fn promotions() {
+    let stm = Stm::new();
+    let var = TVar::new(0);
+
+    // Switched to write context, not aborted
+    stm.atomically(var.write(1));
+    assert_eq!(stm.stats().promotions(), 1);
+    assert_eq!(stm.stats().aborts(), 0);
+
+    // Waiting in read-only context switches it too
+    let waited = Cell::new(false);
+    stm.atomically_fn(|context| {
+        let x = context.read(&var)?;
+        if !waited.replace(true) {
+            return context.retry();
+        }
+        Ok(x)
+    });
+
+    let stats = stm.stats();
+    assert_eq!(stats.promotions(), 2);
+    assert_eq!(stats.user_retries(), 1);
+    assert_eq!(stats.aborts(), 1);
+}
@@ -0,0 +1,155 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use xstm::{
+    Algorithm, Context, NOrec, Stm, StmError, TArc, TBox, Transaction, TransactionExt, Tl2,
+};
+
+// Count the values alive
+struct Item {
+    live: Arc<AtomicUsize>,
+    value: String,
+}
+
+impl Item {
+    fn new(live: &Arc<AtomicUsize>, value: String) -> Self {
+        live.fetch_add(1, Ordering::SeqCst);
+        Item {
+            live: live.clone(),
+            value,
+        }
+    }
+}
+
+impl Clone for Item {
+    fn clone(&self) -> Self {
+        Item::new(&self.live, self.value.clone())
+    }
+}
+
+impl Drop for Item {
+    fn drop(&mut self) {
+        self.live.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn push<A: Algorithm + Sync>(stm: Stm<A>) {
+    let live = Arc::new(AtomicUsize::new(0));
+    let items = TBox::new(Vec::<Item>::new());
+
+    std::thread::scope(|scope| {
+        let threads: Vec<_> = (0..4)
+            .map(|thread| {
+                let (stm, items, live) = (&stm, &items, &live);
+                scope.spawn(move || {
+                    for i in 0..50 {
+                        stm.atomically_fn(|context| {
+                            let mut list = context.read_box(items)?;
+                            list.push(Item::new(live, format!("{thread}-{i}")));
+                            context.write_box(items, list)
+                        });
+                    }
+                })
+            })
+            .collect();
+
+        // The values retired by a thread are left to the others when its thread-locals drop,
+        // only join waits for that
+        for thread in threads {
+            thread.join().unwrap();
+        }
+    });
+
+    let list = stm.atomically_fn(|context| context.read_box(&items));
+    assert_eq!(list.len(), 200);
+    drop(list);
+    drop(items);
+
+    // The replaced lists are freed by the later retirements
+    let scratch = TBox::new(0);
+    for i in 0..1000 {
+        if live.load(Ordering::SeqCst) == 0 {
+            break;
+        }
+        stm.atomically_fn(|context| context.write_box(&scratch, i));
+    }
+    assert_eq!(live.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn reclaim() {
+    push(Stm::new());
+    push(Stm::with_algorithm(Tl2::new().eager()));
+    push(Stm::with_algorithm(NOrec::new()));
+}
+
+fn discard<A: Algorithm>(stm: Stm<A>) {
+    let live = Arc::new(AtomicUsize::new(0));
+    let items = TBox::new(Vec::<Item>::new());
+
+    let result = stm.try_atomically_fn(|context| {
+        context.write_box(&items, vec![Item::new(&live, String::from("x"))])?;
+        context.abort("discarded")
+    });
+    assert_eq!(result, Err::<(), _>("discarded"));
+
+    // Never published, freed without waiting for the epochs
+    assert_eq!(live.load(Ordering::SeqCst), 0);
+}
+
+#[test]
+fn aborted() {
+    discard(Stm::new());
+    discard(Stm::with_algorithm(Tl2::new().eager()));
+    discard(Stm::with_algorithm(NOrec::new()));
+}
+
+// Replace the config, then retry if `retry`
+struct Set<'a> {
+    config: &'a TArc<String>,
+    value: &'static str,
+    retry: bool,
+}
+
+impl Transaction for Set<'_> {
+    type Output = ();
+
+    fn atomically<'var, A: Algorithm>(
+        &'var self,
+        context: &mut Context<'var, A>,
+    ) -> Result<Self::Output, StmError> {
+        context.write_arc(self.config, Arc::new(String::from(self.value)))?;
+
+        if self.retry {
+            return context.retry();
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn arc_rollback() {
+    let stm = Stm::new();
+    let config = TArc::new(String::from("v1"));
+
+    let first = stm.atomically_fn(|context| context.read_arc(&config));
+
+    // The write of the first branch is discarded
+    let v2 = Set {
+        config: &config,
+        value: "v2",
+        retry: true,
+    };
+    let v3 = Set {
+        config: &config,
+        value: "v3",
+        retry: false,
+    };
+    stm.atomically(v2.or_else(v3));
+
+    let latest = stm.atomically_fn(|context| context.read_arc(&config));
+    assert_eq!(*first, "v1");
+    assert_eq!(*latest, "v3");
+}
@@ -0,0 +1,32 @@
+use xstm::{Algorithm, NOrec, Stm, TVar, Tl2};
+
+fn counters<A: Algorithm + Sync>(stm: Stm<A>) {
+    let slots = TVar::new([0u32; 256]);
+
+    std::thread::scope(|scope| {
+        for thread in 0..4 {
+            let (stm, slots) = (&stm, &slots);
+            scope.spawn(move || {
+                for i in 0..100 {
+                    stm.atomically_fn(|context| {
+                        // The second update changes the buffered copy
+                        context.update(slots, |s| s[thread] += 1)?;
+                        context.update(slots, |s| s[255 - i] += 1)
+                    });
+                }
+            });
+        }
+    });
+
+    let slots = stm.atomically(slots.read());
+    assert_eq!(slots[..4], [100, 100, 100, 100]);
+    assert_eq!(slots[200], 4);
+    assert_eq!(slots.iter().sum::<u32>(), 800);
+}
+
+#[test]
+fn update() {
+    counters(Stm::new());
+    counters(Stm::with_algorithm(Tl2::new().eager()));
+    counters(Stm::with_algorithm(NOrec::new()));
+}
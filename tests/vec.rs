@@ -1,5 +1,5 @@
 use std::sync::Arc;
-use xstm::{Context, Stm, StmError, TVar, Transaction};
+use xstm::{Algorithm, Context, Stm, StmError, TVar, Transaction};
 
 const VARS_COUNT: usize = 10;
 
@@ -10,9 +10,9 @@ struct Update<'a> {
 impl<'a> Transaction for Update<'a> {
     type Output = ();
 
-    fn atomically<'this: 'var, 'context, 'var>(
-        &'this self,
-        context: &'context mut Context<'var>,
+    fn atomically<'var, A: Algorithm>(
+        &'var self,
+        context: &mut Context<'var, A>,
     ) -> Result<Self::Output, StmError> {
         for var in self.vars {
             let x = context.read(var)?;
@@ -49,10 +49,8 @@ async fn basic() {
 
         handles.push(handle);
     }
-    let mut results = Vec::new();
-
     for handle in handles {
-        results.push(handle.await.unwrap());
+        handle.await.unwrap();
     }
 
     let vars = Arc::try_unwrap(vars).unwrap();